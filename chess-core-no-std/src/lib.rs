@@ -0,0 +1,42 @@
+//! Checks that chess-core builds and works as `no_std` + `alloc`.
+//!
+//! Cargo unifies features across the packages it builds, so run this crate on its own to make sure
+//! chess-core is built without `std`: `cargo test -p chess-core-no-std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+use chess_core::{board::Board, moves::{generate_legal_moves, perft}};
+
+/// Count the leaf nodes below the position, or [None] if the FEN is invalid.
+pub fn perft_fen(fen: &str, depth: usize) -> Option<u64> {
+    let mut board = Board::new();
+    board.load_fen(fen).ok()?;
+    Some(perft(&board, depth))
+}
+
+/// Play the UCI moves from the starting position and return the resulting FEN.
+pub fn play(moves: &[&str]) -> Option<String> {
+    let mut board = Board::default();
+    for notation in moves {
+        let m = board.parse_uci_move(notation).ok()?;
+        board.make_move(m);
+    }
+    Some(board.as_fen())
+}
+
+/// Legal moves of the position in standard algebraic notation.
+pub fn legal_san(board: &Board) -> Vec<String> {
+    generate_legal_moves(board).into_iter().map(|m| board.san(m)).collect()
+}
+
+#[test]
+fn core_works_without_std() {
+    assert_eq!(perft_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2), Some(2039));
+    assert_eq!(perft_fen("not a fen", 1), None);
+    assert_eq!(play(&["e2e4", "e7e5", "g1f3"]).as_deref(), Some("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"));
+    assert_eq!(legal_san(&Board::default()).len(), 20);
+}
@@ -1,17 +1,18 @@
-use std::str::FromStr;
+use alloc::{string::{String, ToString}, vec::Vec};
+use core::str::FromStr;
 
 use anyhow::{bail, Context};
 
-use super::{file::{File, NUM_BOARD_FILES}, piece::{Piece, Side}, rank::{Rank, NUM_BOARD_RANKS}, square::Square, Board};
+use super::{file::{File, NUM_BOARD_FILES}, piece::{Piece, Side, NUM_PIECE_KINDS, NUM_PIECE_SIDES}, rank::{Rank, NUM_BOARD_RANKS}, square::Square, Board, EMPTY_BITBOARD};
 
 pub const DEFAULT_FEN_START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 pub const NUM_FEN_FIELDS: usize = 6;
 
-const WHITE_KING_SIDE_CASTLING: u8 = 1;
-const WHITE_QUEEN_SIDE_CASTLING: u8 = 2;
-const BLACK_KING_SIDE_CASTLING: u8 = 4;
-const BLACK_QUEEN_SIDE_CASTLING: u8 = 8;
-const ALL_CASTLING: u8 = 15;
+pub const WHITE_KING_SIDE_CASTLING: u8 = 1;
+pub const WHITE_QUEEN_SIDE_CASTLING: u8 = 2;
+pub const BLACK_KING_SIDE_CASTLING: u8 = 4;
+pub const BLACK_QUEEN_SIDE_CASTLING: u8 = 8;
+pub const ALL_CASTLING: u8 = 15;
 
 impl Board {
     pub fn load_fen(&mut self, fen: &str) -> anyhow::Result<()> {
@@ -19,13 +20,14 @@ impl Board {
         if fields.len() != NUM_FEN_FIELDS {
             bail!("invalid number of FEN string fields");
         }
-        let pos_field = fields.get(0).context("FEN string missing placement data")?;
+        let pos_field = fields.first().context("FEN string missing placement data")?;
         let side_field = fields.get(1).context("FEN string missing active side field")?;
         let castling_field = fields.get(2).context("FEN string missing castling field")?;
         let en_passant_field = fields.get(3).context("FEN string missing en passant field")?;
         let halfmove_clock_field = fields.get(4).context("FEN string missing halfmove clock field")?;
         let fullmove_number_field = fields.get(5).context("FEN string missing fullmove number field")?;
 
+        self.bitboards = [[EMPTY_BITBOARD; NUM_PIECE_KINDS]; NUM_PIECE_SIDES];
         let ranks: Vec<String> = pos_field.split('/').map(|s| s.to_string()).collect();
         if ranks.len() != NUM_BOARD_RANKS {
             bail!("invalid number of ranks in FEN string");
@@ -35,6 +37,9 @@ impl Board {
         for part in ranks {
             rank -= 1;
             for c in part.chars() {
+                if file >= NUM_BOARD_FILES {
+                    bail!("too many squares in FEN string rank");
+                }
                 let mask = Square::from_coord(Rank::from(rank), File::from(file)).as_mask();
                 match c {
                     'p' => *self.piece_mut(Side::Black, Piece::Pawn) |= mask,
@@ -57,6 +62,9 @@ impl Board {
                 }
                 file += 1;
             }
+            if file != NUM_BOARD_FILES {
+                bail!("invalid number of squares in FEN string rank");
+            }
             file = 0;
         }
 
@@ -67,7 +75,7 @@ impl Board {
         }
 
         self.castling = 0;
-        if (1..=4).contains(&castling_field.len()) {
+        if castling_field.as_str() != "-" && (1..=4).contains(&castling_field.len()) {
             for c in castling_field.chars() {
                 match c {
                     'K' => self.castling |= WHITE_KING_SIDE_CASTLING,
@@ -89,6 +97,8 @@ impl Board {
 
         self.fullmove_number = fullmove_number_field.as_str().parse::<u16>().context("failed to parse fullmove number")?;
 
+        self.hash = self.compute_hash();
+
         Ok(())
     }
 
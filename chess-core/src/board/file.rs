@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use core::str::FromStr;
 
 use anyhow::bail;
 
@@ -36,7 +36,7 @@ impl File {
 impl From<u8> for File {
     fn from(value: u8) -> Self {
         debug_assert!(value < 8, "file value out of bounds");
-        unsafe { std::mem::transmute(value) }
+        unsafe { core::mem::transmute(value) }
     }
 }
 
@@ -1,6 +1,7 @@
-use std::fmt::Display;
+use alloc::string::String;
+use core::fmt::Display;
 
-use crate::board::{file::{File, NUM_BOARD_FILES}, rank::{Rank, NUM_BOARD_RANKS}};
+use crate::{board::{file::{File, FILE_A, FILE_H, NUM_BOARD_FILES}, rank::{Rank, NUM_BOARD_RANKS}}, zobrist};
 
 use self::{fen::DEFAULT_FEN_START, piece::{Piece, Side, BLACK_BISHOP_UNICODE, BLACK_KING_UNICODE, BLACK_KNIGHT_UNICODE, BLACK_PAWN_UNICODE, BLACK_QUEEN_UNICODE, BLACK_ROOK_UNICODE, NUM_PIECE_KINDS, NUM_PIECE_SIDES, WHITE_BISHOP_UNICODE, WHITE_KING_UNICODE, WHITE_KNIGHT_UNICODE, WHITE_PAWN_UNICODE, WHITE_QUEEN_UNICODE, WHITE_ROOK_UNICODE}, square::{Square, NUM_BOARD_SQUARES, RANK_NAMES}};
 
@@ -17,7 +18,7 @@ pub const EMPTY_BITBOARD: Bitboard = 0;
 
 pub fn bitboard_square_iter(bitboard: Bitboard) -> impl Iterator<Item = Square> {
     let mut bb = bitboard;
-    std::iter::from_fn(move || {
+    core::iter::from_fn(move || {
         if bb > 0 {
             let n = bb.trailing_zeros();
             bb ^= 1u64 << n;
@@ -50,17 +51,19 @@ pub fn display_bitboard(bitboard: Bitboard) -> String {
 #[derive(Clone)]
 pub struct Board {
     /// Piece placement data.
-    bitboards: [[Bitboard; NUM_PIECE_KINDS]; NUM_PIECE_SIDES],
+    pub(crate) bitboards: [[Bitboard; NUM_PIECE_KINDS]; NUM_PIECE_SIDES],
     /// Which side is to move.
-    active_side: Side,
+    pub(crate) active_side: Side,
     /// Availability to castle.
-    castling: u8,
+    pub(crate) castling: u8,
     /// Square over which a pawn hhas just passed while moving two squares.
-    en_passant: Option<Square>,
+    pub(crate) en_passant: Option<Square>,
     /// Number of halfmoves since last capture or pawn advance, used for fifty-move rule.
-    halfmove_clock: u8,
+    pub(crate) halfmove_clock: u8,
     /// Number of full moves, starting at 1. Incriments after Black's move.
-    fullmove_number: u16,
+    pub(crate) fullmove_number: u16,
+    /// Polyglot key of the pieces, castling rights and active side. En passant is added by [Board::hash].
+    pub(crate) hash: u64,
 }
 
 impl Board {
@@ -72,6 +75,7 @@ impl Board {
             en_passant: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            hash: zobrist::turn_key(),
         }
     }
 
@@ -93,11 +97,15 @@ impl Board {
     }
 
     pub fn remove_piece(&mut self, side: Side, piece: Piece, square: Square) {
-        self.bitboards[side as usize][piece as usize] ^= square.as_mask()
+        self.bitboards[side as usize][piece as usize] ^= square.as_mask();
+        self.hash ^= zobrist::piece_key(side, piece, square);
     }
 
     pub fn place_piece(&mut self, side: Side, piece: Piece, square: Square) {
-        self.bitboards[side as usize][piece as usize] |= square.as_mask()
+        if self.bitboards[side as usize][piece as usize] & square.as_mask() == 0 {
+            self.bitboards[side as usize][piece as usize] |= square.as_mask();
+            self.hash ^= zobrist::piece_key(side, piece, square);
+        }
     }
 
     pub fn move_piece(&mut self, side: Side, piece: Piece, from_square: Square, to_square: Square) {
@@ -123,6 +131,78 @@ impl Board {
     pub fn en_passant(&self) -> Option<Square> {
         self.en_passant
     }
+
+    pub fn castling(&self) -> u8 {
+        self.castling
+    }
+
+    pub fn halfmove_clock(&self) -> u8 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+
+    pub fn king_square(&self, side: Side) -> Square {
+        Square(self.piece(side, Piece::King).trailing_zeros() as u8)
+    }
+
+    /// Polyglot key of the position. The en passant file is only included when the active side
+    /// has a pawn that could capture onto it.
+    pub fn hash(&self) -> u64 {
+        match self.en_passant {
+            Some(square) => {
+                let pawns = self.piece(self.active_side, Piece::Pawn);
+                let rank = match self.active_side {
+                    Side::White => Rank::R5,
+                    Side::Black => Rank::R4,
+                }.as_mask();
+                let file = square.file().as_mask();
+                let neighbors = ((file << 1) & !FILE_A) | ((file >> 1) & !FILE_H);
+                if pawns & rank & neighbors != 0 {
+                    self.hash ^ zobrist::en_passant_key(square.file() as usize)
+                } else {
+                    self.hash
+                }
+            },
+            None => self.hash,
+        }
+    }
+
+    /// Mirror the position across the horizontal center of the board and swap the sides, so the
+    /// same position is seen from the other side's perspective.
+    pub fn mirrored(&self) -> Board {
+        let mut board = Board::new();
+        for side in 0..NUM_PIECE_SIDES {
+            for piece in 0..NUM_PIECE_KINDS {
+                board.bitboards[side ^ 1][piece] = self.bitboards[side][piece].swap_bytes();
+            }
+        }
+        board.active_side = self.active_side.other();
+        board.castling = (self.castling >> 2) | ((self.castling & 3) << 2);
+        board.en_passant = self.en_passant.map(|square| square.flip_rank());
+        board.halfmove_clock = self.halfmove_clock;
+        board.fullmove_number = self.fullmove_number;
+        board.hash = board.compute_hash();
+        board
+    }
+
+    /// Recalculate the incremental part of [Board::hash] from scratch.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let mut hash = zobrist::castling_keys(self.castling);
+        if self.active_side == Side::White {
+            hash ^= zobrist::turn_key();
+        }
+        for side in 0..NUM_PIECE_SIDES {
+            for piece in 0..NUM_PIECE_KINDS {
+                for square in bitboard_square_iter(self.bitboards[side][piece]) {
+                    hash ^= zobrist::piece_key(Side::from(side), Piece::from(piece), square);
+                }
+            }
+        }
+        hash
+    }
 }
 
 impl Default for Board {
@@ -134,8 +214,8 @@ impl Default for Board {
 }
 
 impl Display for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}\n", self.as_fen())?;
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{}", self.as_fen())?;
         let mut board = String::with_capacity(NUM_BOARD_SQUARES);
         for rank in (0..NUM_BOARD_RANKS).rev() {
             board.push(RANK_NAMES[rank]);
@@ -172,7 +252,7 @@ impl Display for Board {
 #[test]
 fn board_load_and_query() {
     use self::fen::DEFAULT_FEN_START;
-    use std::str::FromStr;
+    use core::str::FromStr;
     
     let mut board = Board::new();
     let square = Square::from_str("d1").expect("failed to parse notation");
@@ -182,6 +262,6 @@ fn board_load_and_query() {
             assert_eq!(piece, Piece::Queen, "flipped pieces");
             assert_eq!(side, Side::White, "flipped sides");
         },
-        None => assert!(false)
+        None => panic!("missing piece"),
     }
 }
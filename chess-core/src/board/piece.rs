@@ -15,7 +15,7 @@ pub const BLACK_KNIGHT_UNICODE: char = '\u{265E}';
 pub const BLACK_PAWN_UNICODE: char = '\u{265F}';
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Side {
     White = 0,
     Black = 1,
@@ -30,7 +30,7 @@ impl Side {
 impl From<u8> for Side {
     fn from(value: u8) -> Self {
         debug_assert!(value < 2, "side value out of bounds");
-        unsafe { std::mem::transmute(value) }
+        unsafe { core::mem::transmute(value) }
     }
 }
 
@@ -41,7 +41,7 @@ impl From<usize> for Side {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Piece {
     Pawn = 0,
     Knight = 1,
@@ -54,7 +54,7 @@ pub enum Piece {
 impl From<u8> for Piece {
     fn from(value: u8) -> Self {
         debug_assert!(value < 6, "piece value out of bounds");
-        unsafe { std::mem::transmute(value) }
+        unsafe { core::mem::transmute(value) }
     }
 }
 
@@ -1,4 +1,4 @@
-use std::{ops::Deref, str::FromStr};
+use core::{ops::Deref, str::FromStr};
 
 use anyhow::{bail, Context};
 
@@ -30,10 +30,10 @@ impl Square {
     }
 
     pub const fn as_mask(&self) -> Bitboard {
-        0 | (1 << self.0)        
+        1 << self.0
     }
 
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> &'static str {
         SQUARE_NAMES[self.0 as usize]
     }
 
@@ -44,6 +44,16 @@ impl Square {
     pub fn file(&self) -> File {
         File::from((self.0 % 8) as usize)
     }
+
+    /// Mirror the square across the horizontal center of the board, ie. "a1" to "a8".
+    pub const fn flip_rank(&self) -> Self {
+        Self(self.0 ^ 56)
+    }
+
+    /// Mirror the square across the vertical center of the board, ie. "a1" to "h1".
+    pub const fn flip_file(&self) -> Self {
+        Self(self.0 ^ 7)
+    }
 }
 
 impl From<u8> for Square {
@@ -89,17 +99,17 @@ impl FromStr for Square {
             'h' => 7,
             _ => bail!("invalid file char"),
         };
-        match chars.next().context("notation missing rank char")? {
-            '1' => n += 8 * 0,
-            '2' => n += 8 * 1,
-            '3' => n += 8 * 2,
-            '4' => n += 8 * 3,
-            '5' => n += 8 * 4,
-            '6' => n += 8 * 5,
-            '7' => n += 8 * 6,
-            '8' => n += 8 * 7, 
+        n += match chars.next().context("notation missing rank char")? {
+            '1' => 0,
+            '2' => 8,
+            '3' => 8 * 2,
+            '4' => 8 * 3,
+            '5' => 8 * 4,
+            '6' => 8 * 5,
+            '7' => 8 * 6,
+            '8' => 8 * 7,
             _ => bail!("invalid rank char"),
-        }
+        };
         if chars.next().is_some() {
             bail!("invalid notation length")
         }
@@ -124,6 +134,6 @@ fn square_name() {
 fn square_parse() {
     match Square::from_str("c6") {
         Ok(s) => assert_eq!(s, Square::from_coord(Rank::R6, File::C)),
-        Err(e) => assert!(false, "{}", e),        
+        Err(e) => panic!("{}", e),
     }
 }
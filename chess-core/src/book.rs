@@ -0,0 +1,287 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use anyhow::bail;
+#[cfg(feature = "std")]
+use anyhow::Context;
+
+use crate::{board::{piece::Side, Board}, moves::{generate_legal_moves, ShortMove}, pgn::{parse_pgn, GameResult, PgnGame}};
+
+/*
+* Reference: http://hgm.nubati.net/book_format.html
+*/
+
+/// Size of an entry in a Polyglot `.bin` file.
+pub const BOOK_ENTRY_SIZE: usize = 16;
+const DEFAULT_BOOK_MAX_PLY: usize = 24;
+const DEFAULT_BOOK_MIN_GAMES: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    /// Polyglot key of the position, see [Board::hash].
+    pub key: u64,
+    /// Move in Polyglot encoding, see [encode_move].
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn to_bytes(&self) -> [u8; BOOK_ENTRY_SIZE] {
+        let mut bytes = [0u8; BOOK_ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; BOOK_ENTRY_SIZE]) -> Self {
+        Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+}
+
+/// Encode a move the way Polyglot does. Castling is written as the king capturing its own rook.
+pub fn encode_move(m: ShortMove) -> u16 {
+    let src = m.src().0 as u16;
+    let mut dest = m.dest().0 as u16;
+    if m.is_castling() {
+        dest = if dest > src { src + 3 } else { src - 4 };
+    }
+    let promotion = match m.promoted() {
+        Some(piece) => piece as u16,
+        None => 0,
+    };
+    dest | (src << 6) | (promotion << 12)
+}
+
+/// Find the legal move matching a Polyglot encoded move.
+pub fn decode_move(board: &Board, raw_move: u16) -> Option<ShortMove> {
+    generate_legal_moves(board).into_iter().find(|m| encode_move(*m) == raw_move)
+}
+
+/// A Polyglot opening book, sorted by key.
+#[derive(Debug, Default, Clone)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)).then(a.raw_move.cmp(&b.raw_move)));
+        Self { entries }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if !bytes.len().is_multiple_of(BOOK_ENTRY_SIZE) {
+            bail!("book size is not a multiple of {BOOK_ENTRY_SIZE} bytes");
+        }
+        let entries = bytes.chunks_exact(BOOK_ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("failed to read book {}", path.display()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes()).with_context(|| format!("failed to write book {}", path.display()))
+    }
+
+    pub fn entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Legal book moves for the position with their weights, heaviest first.
+    pub fn probe(&self, board: &Board) -> Vec<(ShortMove, u16)> {
+        let key = board.hash();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[start..].iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| decode_move(board, entry.raw_move).map(|m| (m, entry.weight)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BookOptions {
+    /// Number of plies from the start of each game to record.
+    pub max_ply: usize,
+    /// Minimum number of games a move must have been played in to be kept.
+    pub min_games: u32,
+    /// Only record the moves of one side, for a white or black repertoire.
+    pub side: Option<Side>,
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        Self {
+            max_ply: DEFAULT_BOOK_MAX_PLY,
+            min_games: DEFAULT_BOOK_MIN_GAMES,
+            side: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct MoveStats {
+    games: u32,
+    /// Two points for a win and one for a draw, from the perspective of the side that moved.
+    points: u32,
+}
+
+/// Accumulates moves from PGN games into a Polyglot [Book].
+pub struct BookBuilder {
+    options: BookOptions,
+    stats: BTreeMap<(u64, u16), MoveStats>,
+    games: usize,
+    skipped: usize,
+}
+
+impl BookBuilder {
+    pub fn new(options: BookOptions) -> Self {
+        Self {
+            options,
+            stats: BTreeMap::new(),
+            games: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Number of games added to the book.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Number of games skipped because of an invalid start position or move.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Record the moves of a game up to the ply limit. Nothing is recorded if any of those moves are illegal.
+    pub fn add_game(&mut self, game: &PgnGame) -> anyhow::Result<()> {
+        let mut board = game.start()?;
+        let mut played = Vec::with_capacity(self.options.max_ply);
+        for san in game.moves.iter().take(self.options.max_ply) {
+            let m = board.parse_san(san)?;
+            played.push((board.hash(), board.active_side(), encode_move(m)));
+            board.make_move(m);
+        }
+        for (key, side, raw_move) in played {
+            if self.options.side.is_some_and(|s| s != side) {
+                continue;
+            }
+            let stats = self.stats.entry((key, raw_move)).or_default();
+            stats.games += 1;
+            stats.points += match (game.result, side) {
+                (GameResult::WhiteWin, Side::White) | (GameResult::BlackWin, Side::Black) => 2,
+                (GameResult::Draw, _) => 1,
+                _ => 0,
+            };
+        }
+        self.games += 1;
+        Ok(())
+    }
+
+    /// Add every game of the PGN text, skipping invalid ones. Returns the number of games added.
+    pub fn add_pgn(&mut self, text: &str) -> usize {
+        let before = self.games;
+        for game in parse_pgn(text) {
+            if self.add_game(&game).is_err() {
+                self.skipped += 1;
+            }
+        }
+        self.games - before
+    }
+
+    /// Build the book from the recorded moves. Moves below the minimum game count or without any
+    /// points are dropped, and weights are scaled down to fit when needed.
+    pub fn build(&self) -> Book {
+        let kept: Vec<(&(u64, u16), &MoveStats)> = self.stats.iter()
+            .filter(|(_, stats)| stats.games >= self.options.min_games && stats.points > 0)
+            .collect();
+        let max_points = kept.iter().map(|(_, stats)| stats.points).max().unwrap_or(0) as u64;
+        let entries = kept.into_iter().map(|(&(key, raw_move), stats)| {
+            let weight = if max_points > u16::MAX as u64 {
+                ((stats.points as u64 * u16::MAX as u64) / max_points).max(1) as u16
+            } else {
+                stats.points as u16
+            };
+            BookEntry { key, raw_move, weight, learn: 0 }
+        }).collect();
+        Book::from_entries(entries)
+    }
+}
+
+/// Build a Polyglot book from the games of a PGN collection.
+pub fn build_book(pgn: &str, options: BookOptions) -> Book {
+    let mut builder = BookBuilder::new(options);
+    builder.add_pgn(pgn);
+    builder.build()
+}
+
+#[test]
+fn build_and_probe_book() {
+    let pgn = r#"
+[Result "1-0"]
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Result "1/2-1/2"]
+1. e4 c5 2. Nf3 1/2-1/2
+
+[Result "0-1"]
+1. d4 d5 0-1
+
+[Result "1-0"]
+1. e4 e5 2. Bc4 1-0
+"#;
+    let book = build_book(pgn, BookOptions { max_ply: 3, min_games: 1, side: None });
+    let start = Board::default();
+    let moves: Vec<(String, u16)> = book.probe(&start).into_iter().map(|(m, w)| (m.to_string(), w)).collect();
+    assert_eq!(moves, vec![("e2e4".to_string(), 5)], "d2d4 only lost and should be dropped");
+
+    let reloaded = Book::from_bytes(&book.to_bytes()).expect("failed to reload book");
+    assert_eq!(reloaded.entries(), book.entries());
+
+    let white = build_book(pgn, BookOptions { max_ply: 3, min_games: 2, side: Some(Side::White) });
+    assert!(white.entries().iter().all(|entry| entry.key != {
+        let mut board = Board::default();
+        board.make_move(board.parse_uci_move("e2e4").unwrap());
+        board.hash()
+    }), "black moves should not be recorded");
+    assert_eq!(white.len(), 1, "only 1. e4 was played in at least two games");
+}
+
+#[test]
+fn encode_castling_as_king_takes_rook() {
+    let mut board = Board::new();
+    board.load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").expect("failed to load fen");
+    let m = board.parse_uci_move("e1g1").expect("castling should be legal");
+    assert_eq!(encode_move(m), 7 | (4 << 6));
+    assert_eq!(decode_move(&board, encode_move(m)), Some(m));
+}
@@ -0,0 +1,217 @@
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::fmt::Display;
+
+use anyhow::{bail, Context};
+
+use crate::{board::Board, moves::ShortMove};
+
+/*
+* Reference: https://www.chessprogramming.org/Extended_Position_Description
+*/
+
+/// Number of position fields in an EPD record, a FEN without the move counters.
+pub const NUM_EPD_FIELDS: usize = 4;
+
+/// An opcode and its operands, ie. `bm Nf3 Qd1;` or `id "WAC.001";`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+/// A position with its operations. Operations are kept in their original order so the record can
+/// be written back unchanged.
+#[derive(Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: Vec<EpdOperation>,
+}
+
+/// Opcodes whose operand is a string and is always quoted when written.
+fn is_string_opcode(opcode: &str) -> bool {
+    opcode == "id" || (opcode.len() == 2 && opcode.starts_with('c') && opcode.as_bytes()[1].is_ascii_digit())
+}
+
+impl Epd {
+    /// Parse a single EPD record. The `hmvc` and `fmvn` operations set the move counters, which are
+    /// otherwise 0 and 1.
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        let line = line.trim();
+        let mut rest = line;
+        let mut fields = Vec::with_capacity(NUM_EPD_FIELDS);
+        for _ in 0..NUM_EPD_FIELDS {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == 0 {
+                bail!("EPD record has fewer than {NUM_EPD_FIELDS} fields: {line}");
+            }
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        let operations = parse_operations(rest).with_context(|| format!("invalid EPD operations: {line}"))?;
+
+        let mut epd = Self { board: Board::new(), operations };
+        let halfmove_clock = epd.operand("hmvc").unwrap_or("0");
+        let fullmove_number = epd.operand("fmvn").unwrap_or("1");
+        let fen = format!("{} {halfmove_clock} {fullmove_number}", fields.join(" "));
+        epd.board.load_fen(&fen).with_context(|| format!("invalid EPD position: {line}"))?;
+        Ok(epd)
+    }
+
+    pub fn operation(&self, opcode: &str) -> Option<&EpdOperation> {
+        self.operations.iter().find(|op| op.opcode == opcode)
+    }
+
+    /// First operand of the operation.
+    pub fn operand(&self, opcode: &str) -> Option<&str> {
+        self.operation(opcode)?.operands.first().map(String::as_str)
+    }
+
+    /// Replace the operands of the operation, or append it if missing.
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|op| op.opcode == opcode) {
+            Some(op) => op.operands = operands,
+            None => self.operations.push(EpdOperation { opcode: opcode.to_string(), operands }),
+        }
+    }
+
+    pub fn remove_operation(&mut self, opcode: &str) {
+        self.operations.retain(|op| op.opcode != opcode);
+    }
+
+    /// Identifier from the `id` opcode.
+    pub fn id(&self) -> Option<&str> {
+        self.operand("id")
+    }
+
+    /// Comment from one of the `c0` to `c9` opcodes.
+    pub fn comment(&self, n: u8) -> Option<&str> {
+        self.operand(&format!("c{n}"))
+    }
+
+    /// Analysis depth from the `acd` opcode.
+    pub fn analysis_depth(&self) -> Option<u32> {
+        self.operand("acd")?.parse().ok()
+    }
+
+    /// Moves of a SAN move list operation resolved against the position, ie. `bm` or `am`.
+    /// An empty list is returned if the operation is missing.
+    pub fn moves(&self, opcode: &str) -> anyhow::Result<Vec<ShortMove>> {
+        let Some(op) = self.operation(opcode) else { return Ok(Vec::new()) };
+        op.operands.iter().map(|san| self.board.parse_san(san).with_context(|| format!("invalid {opcode} move: {san}"))).collect()
+    }
+
+    /// Best moves from the `bm` opcode.
+    pub fn best_moves(&self) -> anyhow::Result<Vec<ShortMove>> {
+        self.moves("bm")
+    }
+
+    /// Moves to avoid from the `am` opcode.
+    pub fn avoid_moves(&self) -> anyhow::Result<Vec<ShortMove>> {
+        self.moves("am")
+    }
+}
+
+fn parse_operations(text: &str) -> anyhow::Result<Vec<EpdOperation>> {
+    let mut operations = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut opcode = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+            opcode.push(c);
+        }
+        if opcode.is_empty() {
+            bail!("empty opcode");
+        }
+        let mut operands = Vec::new();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next() {
+                Some(';') => break,
+                Some('"') => {
+                    let mut operand = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => operand.extend(chars.next()),
+                            Some(c) => operand.push(c),
+                            None => bail!("unterminated string operand for {opcode}"),
+                        }
+                    }
+                    operands.push(operand);
+                },
+                Some(c) => {
+                    let mut operand = String::from(c);
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+                        operand.push(c);
+                    }
+                    operands.push(operand);
+                },
+                // Lenient about a missing semicolon after the last operation.
+                None => break,
+            }
+        }
+        operations.push(EpdOperation { opcode, operands });
+    }
+    Ok(operations)
+}
+
+impl Display for EpdOperation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.opcode)?;
+        for operand in self.operands.iter() {
+            let quoted = is_string_opcode(&self.opcode) || operand.is_empty() || operand.contains(|c: char| c.is_whitespace() || c == ';' || c == '"');
+            if quoted {
+                write!(f, " \"{}\"", operand.replace('\\', "\\\\").replace('"', "\\\""))?;
+            } else {
+                write!(f, " {operand}")?;
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let fen = self.board.as_fen();
+        let fields: Vec<&str> = fen.split(' ').take(NUM_EPD_FIELDS).collect();
+        write!(f, "{}", fields.join(" "))?;
+        for op in self.operations.iter() {
+            write!(f, " {op}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse every record of an EPD file, skipping blank lines and `#` comments.
+pub fn parse_epd(text: &str) -> anyhow::Result<Vec<Epd>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(n, line)| Epd::parse(line).with_context(|| format!("line {}", n + 1)))
+        .collect()
+}
+
+#[test]
+fn epd_round_trip() {
+    let line = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001"; c0 "mate in 3"; acd 12;"#;
+    let epd = Epd::parse(line).expect("failed to parse EPD");
+    assert_eq!(epd.board.as_fen(), "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1");
+    assert_eq!(epd.id(), Some("WAC.001"));
+    assert_eq!(epd.comment(0), Some("mate in 3"));
+    assert_eq!(epd.analysis_depth(), Some(12));
+    assert_eq!(epd.best_moves().expect("bm should be legal").iter().map(ShortMove::to_string).collect::<Vec<_>>(), ["g3g6"]);
+    assert!(epd.avoid_moves().expect("missing am is empty").is_empty());
+    assert_eq!(epd.to_string(), line);
+
+    let mut epd = Epd::parse("8/8/8/8/8/4K3/8/4k3 b - - am Kd1 Kf1; hmvc 12; fmvn 40").expect("failed to parse EPD");
+    assert_eq!(epd.board.as_fen(), "8/8/8/8/8/4K3/8/4k3 b - - 12 40");
+    assert_eq!(epd.avoid_moves().expect("am should be legal").len(), 2);
+    epd.set_operation("id", ["king moves".to_string()].to_vec());
+    assert_eq!(epd.to_string(), r#"8/8/8/8/8/4K3/8/4k3 b - - am Kd1 Kf1; hmvc 12; fmvn 40; id "king moves";"#);
+    assert!(Epd::parse("8/8/8/8/8/8/4K3/4k3 b -").is_err());
+}
@@ -1,3 +1,5 @@
+use alloc::{string::String, vec, vec::Vec};
+
 use crate::{board::Board, moves::ShortMove};
 
 pub struct GameState {
@@ -19,11 +21,21 @@ impl GameState {
     pub fn board(&self) -> &Board {
         &self.current
     }
+
+    pub fn history(&self) -> &GameHistory {
+        &self.history
+    }
+
+    /// Play a legal move on the current board and record it.
+    pub fn play(&mut self, m: ShortMove) {
+        self.current.make_move(m);
+        self.history.moves.push(m);
+    }
 }
 
 pub struct GameHistory {
-    start: Board,
-    moves: Vec<ShortMove>,
+    pub(crate) start: Board,
+    pub(crate) moves: Vec<ShortMove>,
 }
 
 impl GameHistory {
@@ -32,4 +44,21 @@ impl GameHistory {
         board.load_fen(start.as_str())?;
         Ok(Self { start: board, moves })
     }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn moves(&self) -> &[ShortMove] {
+        &self.moves
+    }
+
+    /// Moves the other history adds, if it starts from the same position and plays every move of
+    /// this one first.
+    pub fn continued_by<'a>(&self, other: &'a GameHistory) -> Option<&'a [ShortMove]> {
+        if self.start.as_fen() != other.start.as_fen() {
+            return None;
+        }
+        other.moves.strip_prefix(self.moves.as_slice())
+    }
 }
@@ -1,6 +1,24 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod board;
+pub mod book;
+pub mod epd;
 pub mod game;
 pub mod magic;
 pub mod moves;
+pub mod pgn;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod tablebase;
+/// The KPK generator of the build script, shared so the bitbase and its probe agree on the layout.
+#[allow(dead_code, clippy::needless_range_loop)]
+mod tables {
+    pub mod king;
+    pub mod kpk;
+    pub mod pawn;
+}
+pub mod zobrist;
 
 include!(concat!(env!("OUT_DIR"), "/tables.rs"));
@@ -1,3 +1,5 @@
+use alloc::{vec, vec::Vec};
+
 use crate::board::{square::NUM_BOARD_SQUARES, Bitboard};
 
 /*
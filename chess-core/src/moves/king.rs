@@ -1,4 +1,4 @@
-use crate::{board::{bitboard_square_iter, piece::Piece, square::Square, Board}, KING_MOVE_TABLE};
+use crate::{board::{bitboard_square_iter, fen::{BLACK_KING_SIDE_CASTLING, BLACK_QUEEN_SIDE_CASTLING, WHITE_KING_SIDE_CASTLING, WHITE_QUEEN_SIDE_CASTLING}, piece::{Piece, Side}, square::Square, Board}, KING_MOVE_TABLE};
 
 use super::{ShortMove, MoveList};
 
@@ -6,6 +6,9 @@ impl MoveList {
     pub fn add_king_moves(&mut self, board: &Board) {
         let active_side = board.active_side();
         let king = board.piece(active_side, Piece::King);
+        if king == 0 {
+            return;
+        }
         let from = Square(king.trailing_zeros() as u8);
         let attacks = KING_MOVE_TABLE[from.0 as usize] & !board.side(active_side);
         for dest in bitboard_square_iter(attacks) {
@@ -17,5 +20,39 @@ impl MoveList {
         }
     }
 
-    // TODO: add castling moves
+    /// Castling is only generated when the king is not in check and does not pass through an
+    /// attacked square, so these moves are legal.
+    pub fn add_castling_moves(&mut self, board: &Board) {
+        let active_side = board.active_side();
+        let (king_side, queen_side, rank) = match active_side {
+            Side::White => (WHITE_KING_SIDE_CASTLING, WHITE_QUEEN_SIDE_CASTLING, 0),
+            Side::Black => (BLACK_KING_SIDE_CASTLING, BLACK_QUEEN_SIDE_CASTLING, 56),
+        };
+        if board.castling() & (king_side | queen_side) == 0 {
+            return;
+        }
+        let king = Square(rank + 4);
+        if board.piece(active_side, Piece::King) & king.as_mask() == 0 || board.in_check() {
+            return;
+        }
+        let rooks = board.piece(active_side, Piece::Rook);
+        let occupied = board.occupied();
+        let enemy = active_side.other();
+        if board.castling() & king_side != 0
+            && rooks & Square(rank + 7).as_mask() != 0
+            && occupied & (Square(rank + 5).as_mask() | Square(rank + 6).as_mask()) == 0
+            && !board.is_attacked(Square(rank + 5), enemy)
+            && !board.is_attacked(Square(rank + 6), enemy)
+        {
+            self.push(ShortMove::new(king, Square(rank + 6), ShortMove::CASTLING_FLAG));
+        }
+        if board.castling() & queen_side != 0
+            && rooks & Square(rank).as_mask() != 0
+            && occupied & (Square(rank + 1).as_mask() | Square(rank + 2).as_mask() | Square(rank + 3).as_mask()) == 0
+            && !board.is_attacked(Square(rank + 3), enemy)
+            && !board.is_attacked(Square(rank + 2), enemy)
+        {
+            self.push(ShortMove::new(king, Square(rank + 2), ShortMove::CASTLING_FLAG));
+        }
+    }
 }
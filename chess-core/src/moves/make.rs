@@ -0,0 +1,85 @@
+use crate::{board::{fen::{BLACK_KING_SIDE_CASTLING, BLACK_QUEEN_SIDE_CASTLING, WHITE_KING_SIDE_CASTLING, WHITE_QUEEN_SIDE_CASTLING}, piece::{Piece, Side}, square::Square, Board}, zobrist};
+
+use super::ShortMove;
+
+/// Castling rights lost when a piece moves from or to the square.
+const fn castling_lost(square: u8) -> u8 {
+    match square {
+        0 => WHITE_QUEEN_SIDE_CASTLING,
+        4 => WHITE_KING_SIDE_CASTLING | WHITE_QUEEN_SIDE_CASTLING,
+        7 => WHITE_KING_SIDE_CASTLING,
+        56 => BLACK_QUEEN_SIDE_CASTLING,
+        60 => BLACK_KING_SIDE_CASTLING | BLACK_QUEEN_SIDE_CASTLING,
+        63 => BLACK_KING_SIDE_CASTLING,
+        _ => 0,
+    }
+}
+
+impl Board {
+    /// Apply the move to the board. The move is expected to be at least pseudo-legal, ie. from
+    /// [super::generate_moves].
+    pub fn make_move(&mut self, m: ShortMove) {
+        let side = self.active_side;
+        let enemy = side.other();
+        let (src, dest) = (m.src(), m.dest());
+        let (_, piece) = self.square(src).expect("can not move from empty space");
+
+        if m.is_en_pasant() {
+            let captured = match side {
+                Side::White => Square(dest.0 - 8),
+                Side::Black => Square(dest.0 + 8),
+            };
+            self.remove_piece(enemy, Piece::Pawn, captured);
+        } else if m.is_capturing() {
+            let (_, captured) = self.square(dest).expect("can not capture empty space");
+            self.remove_piece(enemy, captured, dest);
+        }
+
+        if m.is_castling() {
+            let (rook_src, rook_dest) = if dest.0 > src.0 {
+                (Square(src.0 + 3), Square(src.0 + 1))
+            } else {
+                (Square(src.0 - 4), Square(src.0 - 1))
+            };
+            self.move_piece(side, Piece::Rook, rook_src, rook_dest);
+        }
+
+        self.remove_piece(side, piece, src);
+        self.place_piece(side, m.promoted().unwrap_or(piece), dest);
+
+        if piece == Piece::Pawn || m.is_capturing() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+
+        self.en_passant = if piece == Piece::Pawn && src.0.abs_diff(dest.0) == 16 {
+            Some(Square((src.0 + dest.0) / 2))
+        } else {
+            None
+        };
+
+        let castling = self.castling & !(castling_lost(src.0) | castling_lost(dest.0));
+        self.hash ^= zobrist::castling_keys(self.castling ^ castling);
+        self.castling = castling;
+
+        if side == Side::Black {
+            self.fullmove_number += 1;
+        }
+        self.active_side = enemy;
+        self.hash ^= zobrist::turn_key();
+    }
+}
+
+#[test]
+fn make_move_matches_fen() {
+    use crate::moves::generate_legal_moves;
+
+    let mut board = Board::default();
+    for notation in ["e2e4", "c7c5", "g1f3", "d7d6", "f1b5", "c8d7", "e1g1"] {
+        let m = generate_legal_moves(&board).into_iter().find(|m| m.to_string() == notation).expect("move should be legal");
+        board.make_move(m);
+    }
+    assert_eq!(board.as_fen(), "rn1qkbnr/pp1bpppp/3p4/1Bp5/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 4");
+    assert_eq!(board.hash(), board.compute_hash(), "incremental hash drifted");
+}
@@ -1,10 +1,13 @@
-use std::{fmt::Debug, ops::Deref};
+use core::fmt::{Debug, Display};
+use core::ops::Deref;
 
-use crate::{board::{bitboard_square_iter, piece::{Piece, Side}, rank::Rank, square::Square, Bitboard, Board, EMPTY_BITBOARD}, PAWN_ATTACK_TABLE, PAWN_MOVE_TABLE};
+use crate::{board::{piece::{Piece, Side}, square::Square, Bitboard, Board}, BISHOP_ATTACK_TABLE, BISHOP_MAGIC_TABLE, KING_MOVE_TABLE, KNIGHT_MOVE_TABLE, PAWN_ATTACK_TABLE, ROOK_ATTACK_TABLE, ROOK_MAGIC_TABLE};
 
 pub mod bishop;
 pub mod king;
 pub mod knight;
+pub mod make;
+pub mod notation;
 pub mod pawn;
 pub mod queen;
 pub mod rook;
@@ -19,10 +22,81 @@ pub fn generate_moves(board: &Board) -> MoveList {
     moves.add_rook_moves(board);
     moves.add_queen_moves(board);
     moves.add_king_moves(board);
-    // TODO: add castling moves
+    moves.add_castling_moves(board);
     moves
 }
 
+/// Generate legal moves by filtering out the pseudo-legal moves that leave the king in check.
+pub fn generate_legal_moves(board: &Board) -> MoveList {
+    let mut legal = MoveList::new();
+    let side = board.active_side();
+    for m in generate_moves(board) {
+        let mut next = board.clone();
+        next.make_move(m);
+        if !next.is_attacked(next.king_square(side), side.other()) {
+            legal.push(m);
+        }
+    }
+    legal
+}
+
+/// Count the leaf nodes of the legal move tree to the given depth.
+pub fn perft(board: &Board, depth: usize) -> u64 {
+    let moves = generate_legal_moves(board);
+    if depth <= 1 {
+        return if depth == 1 { moves.len() as u64 } else { 1 };
+    }
+    moves.into_iter().map(|m| {
+        let mut next = board.clone();
+        next.make_move(m);
+        perft(&next, depth - 1)
+    }).sum()
+}
+
+/// Squares a bishop attacks from the square, stopping at the first blocker in each direction.
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    BISHOP_ATTACK_TABLE[BISHOP_MAGIC_TABLE[square.0 as usize].as_index(occupied)]
+}
+
+/// Squares a rook attacks from the square, stopping at the first blocker in each direction.
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    ROOK_ATTACK_TABLE[ROOK_MAGIC_TABLE[square.0 as usize].as_index(occupied)]
+}
+
+impl Board {
+    /// All pieces of either side attacking the square, given the occupancy used for sliding pieces.
+    pub fn attackers(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        let sq = square.0 as usize;
+        let bishop_attacks = bishop_attacks(square, occupied);
+        let rook_attacks = rook_attacks(square, occupied);
+        let diagonal = self.piece(Side::White, Piece::Bishop) | self.piece(Side::Black, Piece::Bishop)
+            | self.piece(Side::White, Piece::Queen) | self.piece(Side::Black, Piece::Queen);
+        let straight = self.piece(Side::White, Piece::Rook) | self.piece(Side::Black, Piece::Rook)
+            | self.piece(Side::White, Piece::Queen) | self.piece(Side::Black, Piece::Queen);
+        (PAWN_ATTACK_TABLE[Side::White as usize][sq] & self.piece(Side::Black, Piece::Pawn))
+            | (PAWN_ATTACK_TABLE[Side::Black as usize][sq] & self.piece(Side::White, Piece::Pawn))
+            | (KNIGHT_MOVE_TABLE[sq] & (self.piece(Side::White, Piece::Knight) | self.piece(Side::Black, Piece::Knight)))
+            | (KING_MOVE_TABLE[sq] & (self.piece(Side::White, Piece::King) | self.piece(Side::Black, Piece::King)))
+            | (bishop_attacks & diagonal)
+            | (rook_attacks & straight)
+    }
+
+    /// Checks if any piece of the given side attacks the square.
+    pub fn is_attacked(&self, square: Square, by: Side) -> bool {
+        self.attackers(square, self.occupied()) & self.side(by) != 0
+    }
+
+    /// Enemy pieces giving check to the active side.
+    pub fn checkers(&self) -> Bitboard {
+        let side = self.active_side();
+        self.attackers(self.king_square(side), self.occupied()) & self.side(side.other())
+    }
+
+    pub fn in_check(&self) -> bool {
+        self.checkers() != 0
+    }
+}
+
 /// Theoretical max number of possible legal moves.
 pub const MAX_LEGAL_MOVES: usize = 218;
 
@@ -34,28 +108,32 @@ impl ShortMove {
     const FROM_MASK: u16 = 0b0000_0000_0011_1111;
     const DEST_OFFSET: usize = 6;
     const DEST_MASK: u16 = 0b0000_1111_1100_0000;
-    const FLAGS_OFFSET: usize = 12;
     const FLAGS_MASK: u16 = 0b1111_0000_0000_0000;
     pub const CAPTURE_FLAG: u16 = 0b0001_0000_0000_0000;
     pub const CASTLING_FLAG: u16 = 0b0010_0000_0000_0000;
     pub const EN_PASANT_FLAG: u16 = 0b0100_0000_0000_0000;
     const PROMOTION_MASK: u16 = 0b1110_0000_0000_0000;
+    const PROMOTION_FLAG: u16 = 0b1000_0000_0000_0000;
     pub const KNIGHT_PROMOTION_FLAG: u16 = 0b1000_0000_0000_0000;
     pub const BISHOP_PROMOTION_FLAG: u16 = 0b1010_0000_0000_0000;
     pub const ROOK_PROMOTION_FLAG: u16 = 0b1100_0000_0000_0000;
     pub const QUEEN_PROMOTION_FLAG: u16 = 0b1110_0000_0000_0000;
     pub const INVALID: ShortMove = ShortMove(0);
 
+    /// Create a move. Flags are the `*_FLAG` constants, which are already in position.
     pub fn new(src: Square, dest: Square, flags: u16) -> Self {
-        Self(src.0 as u16 | ((dest.0 as u16) << Self::DEST_OFFSET) | (flags << Self::FLAGS_OFFSET))
+        Self(src.0 as u16 | ((dest.0 as u16) << Self::DEST_OFFSET) | (flags & Self::FLAGS_MASK))
     }
 
     /// Checks validity of [ShortMove]. Does not necessarily check move for legality.
     pub fn is_valid(&self) -> bool {
+        if *self == Self::INVALID {
+            return false;
+        }
         debug_assert!(self.src() != self.dest(), "src and dest should not be the same");
-        debug_assert!((self.is_en_pasant() && self.is_capturing()) && self.is_capturing(), "all en pasant should also be captures"); 
+        debug_assert!(!self.is_en_pasant() || self.is_capturing(), "all en pasant should also be captures");
         debug_assert!(!(self.is_capturing() && self.is_castling()), "should not be able to castle and capture simultaneously");
-        *self != Self::INVALID
+        true
     }
 
     pub fn src(&self) -> Square {
@@ -66,8 +144,9 @@ impl ShortMove {
         Square::from((self.0 >> Self::DEST_OFFSET) & 0x3F)
     }
 
+    /// Promotions reuse the castling and en passant bits, so those are only meaningful when the
+    /// promotion bit is clear.
     pub fn promoted(&self) -> Option<Piece> {
-        // TODO: ensure that flags dont conflict
         match self.0 & Self::PROMOTION_MASK {
             Self::KNIGHT_PROMOTION_FLAG => Some(Piece::Knight),
             Self::BISHOP_PROMOTION_FLAG => Some(Piece::Bishop),
@@ -82,21 +161,25 @@ impl ShortMove {
     }
 
     pub fn is_en_pasant(&self) -> bool {
-        self.0 & Self::EN_PASANT_FLAG != 0
+        self.0 & (Self::PROMOTION_FLAG | Self::EN_PASANT_FLAG) == Self::EN_PASANT_FLAG
     }
 
     pub fn is_castling(&self) -> bool {
-        self.0 & Self::CASTLING_FLAG != 0
+        self.0 & (Self::PROMOTION_FLAG | Self::CASTLING_FLAG) == Self::CASTLING_FLAG
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.0 & Self::PROMOTION_FLAG != 0
     }
 
     pub fn set_src(&mut self, src: Square) {
         self.0 &= !Self::FROM_MASK;
-        self.0 |= (src.0 << Self::FROM_OFFSET) as u16;
+        self.0 |= (src.0 as u16) << Self::FROM_OFFSET;
     }
 
     pub fn set_dest(&mut self, dest: Square) {
         self.0 &= !Self::DEST_MASK;
-        self.0 |= (dest.0 << Self::DEST_OFFSET) as u16;
+        self.0 |= (dest.0 as u16) << Self::DEST_OFFSET;
     }
 }
 
@@ -120,11 +203,28 @@ impl Deref for ShortMove {
 }
 
 impl Debug for ShortMove {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "ShortMove({:#b})", self.0)
     }
 }
 
+/// Formats the move in UCI long algebraic notation, ie. "e2e4" or "e7e8q".
+impl Display for ShortMove {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if !self.is_valid() {
+            return write!(f, "0000");
+        }
+        write!(f, "{}{}", self.src().name(), self.dest().name())?;
+        match self.promoted() {
+            Some(Piece::Knight) => write!(f, "n"),
+            Some(Piece::Bishop) => write!(f, "b"),
+            Some(Piece::Rook) => write!(f, "r"),
+            Some(Piece::Queen) => write!(f, "q"),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct MoveList {
     list: [ShortMove; MAX_LEGAL_MOVES],
@@ -133,9 +233,8 @@ pub struct MoveList {
 
 impl MoveList {
     pub fn new() -> Self {
-        Self { 
-            // PERF: IDK if this is actually faster than zeroed?
-            list: unsafe { [std::mem::MaybeUninit::uninit().assume_init(); MAX_LEGAL_MOVES] },
+        Self {
+            list: [ShortMove::INVALID; MAX_LEGAL_MOVES],
             count: 0,
         }
     }
@@ -144,39 +243,33 @@ impl MoveList {
         self.count
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
     pub fn push(&mut self, short_move: ShortMove) {
+        debug_assert!(self.count < MAX_LEGAL_MOVES, "exceded max legal moves");
         self.list[self.count] = short_move;
         self.count += 1;
-        debug_assert!(self.count <= MAX_LEGAL_MOVES, "exceded max legal moves");
     }
 
     pub fn get(&self, index: usize) -> ShortMove {
-        debug_assert!(index <= MAX_LEGAL_MOVES, "index excedes max legal moves");
-        debug_assert!(index <= self.count, "index out of bounds");
+        debug_assert!(index < self.count, "index out of bounds");
         self.list[index]
     }
 
     pub fn get_mut(&mut self, index: usize) -> &mut ShortMove {
-        debug_assert!(index <= MAX_LEGAL_MOVES, "index excedes max legal moves");
-        debug_assert!(index <= self.count, "index out of bounds");
+        debug_assert!(index < self.count, "index out of bounds");
         &mut self.list[index]
     }
 
     pub fn swap(&mut self, a: usize, b: usize) {
-        debug_assert!((a <= self.count) && (b <= self.count), "index out of bounds");
+        debug_assert!((a < self.count) && (b < self.count), "index out of bounds");
         self.list.swap(a, b) // PERF: use nightly swap_unchecked?
     }
 
-    pub fn into_iter(self) -> impl Iterator<Item = ShortMove> {
-        let mut n = 0;
-        std::iter::from_fn(move || {
-            if n < self.count {
-                Some(self.get(n))
-            } else {
-                n += 1;
-                None
-            }
-        })
+    pub fn iter(&self) -> core::slice::Iter<'_, ShortMove> {
+        self.as_slice().iter()
     }
 
     pub fn as_slice(&self) -> &[ShortMove] {
@@ -184,6 +277,36 @@ impl MoveList {
     }
 
     pub fn contains(&self, m: &ShortMove) -> bool {
-        self.list.as_slice().contains(m)
+        self.as_slice().contains(m)
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = ShortMove;
+    type IntoIter = core::iter::Take<core::array::IntoIter<ShortMove, MAX_LEGAL_MOVES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter().take(self.count)
+    }
+}
+
+#[test]
+fn perft_known_positions() {
+    let cases = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3, 8_902),
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2, 2_039),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2_812),
+        ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3, 9_467),
+    ];
+    for (fen, depth, nodes) in cases {
+        let mut board = Board::new();
+        board.load_fen(fen).expect("failed to load fen");
+        assert_eq!(perft(&board, depth), nodes, "{fen}");
     }
 }
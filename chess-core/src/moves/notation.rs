@@ -0,0 +1,174 @@
+use alloc::{format, string::String, vec::Vec};
+use core::str::FromStr;
+
+use anyhow::{bail, Context};
+
+use crate::board::{piece::Piece, square::Square, Board};
+
+use super::{generate_legal_moves, ShortMove};
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    match c {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+fn piece_char(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+impl Board {
+    /// Find the legal move given in UCI long algebraic notation, ie. "e2e4" or "e7e8q".
+    pub fn parse_uci_move(&self, notation: &str) -> anyhow::Result<ShortMove> {
+        if !notation.is_ascii() || !(4..=5).contains(&notation.len()) {
+            bail!("invalid UCI move length: {notation}");
+        }
+        let src = Square::from_str(&notation[0..2])?;
+        let dest = Square::from_str(&notation[2..4])?;
+        let promoted = match notation[4..].chars().next() {
+            None => None,
+            Some('n') => Some(Piece::Knight),
+            Some('b') => Some(Piece::Bishop),
+            Some('r') => Some(Piece::Rook),
+            Some('q') => Some(Piece::Queen),
+            Some(c) => bail!("invalid UCI promotion char: {c}"),
+        };
+        generate_legal_moves(self)
+            .into_iter()
+            .find(|m| m.src() == src && m.dest() == dest && m.promoted() == promoted)
+            .with_context(|| format!("illegal move: {notation}"))
+    }
+
+    /// Find the legal move given in standard algebraic notation, ie. "Nf3", "exd5" or "O-O".
+    /// Check, mate and annotation suffixes are ignored.
+    pub fn parse_san(&self, san: &str) -> anyhow::Result<ShortMove> {
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = generate_legal_moves(self);
+        let castling = match text {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castling {
+            return legal
+                .into_iter()
+                .find(|m| m.is_castling() && (m.dest().0 > m.src().0) == king_side)
+                .with_context(|| format!("illegal move: {san}"));
+        }
+
+        let (text, promoted) = match text.char_indices().last() {
+            Some((i, c)) if text.len() > 2 && (c == 'N' || c == 'B' || c == 'R' || c == 'Q') => {
+                (text[..i].trim_end_matches('='), piece_from_char(c))
+            },
+            _ => (text, None),
+        };
+        if !text.is_ascii() || text.len() < 2 {
+            bail!("invalid SAN move: {san}");
+        }
+        let dest = Square::from_str(&text[text.len() - 2..]).with_context(|| format!("invalid SAN move: {san}"))?;
+        let mut prefix = text[..text.len() - 2].trim_end_matches('x').chars().peekable();
+        let piece = match prefix.peek().copied().and_then(piece_from_char) {
+            Some(piece) => {
+                prefix.next();
+                piece
+            },
+            None => Piece::Pawn,
+        };
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in prefix {
+            match c {
+                'a'..='h' => from_file = Some(c as u8 - b'a'),
+                '1'..='8' => from_rank = Some(c as u8 - b'1'),
+                _ => bail!("invalid SAN move: {san}"),
+            }
+        }
+
+        let mut found = None;
+        for m in legal {
+            let Some((_, moved)) = self.square(m.src()) else { continue };
+            if moved != piece || m.dest() != dest || m.promoted() != promoted || m.is_castling() {
+                continue;
+            }
+            if from_file.is_some_and(|f| f != m.src().0 % 8) || from_rank.is_some_and(|r| r != m.src().0 / 8) {
+                continue;
+            }
+            if found.is_some() {
+                bail!("ambiguous SAN move: {san}");
+            }
+            found = Some(m);
+        }
+        found.with_context(|| format!("illegal move: {san}"))
+    }
+
+    /// Format a legal move in standard algebraic notation.
+    pub fn san(&self, m: ShortMove) -> String {
+        let mut san = String::with_capacity(8);
+        let (_, piece) = self.square(m.src()).expect("can not move from empty space");
+        if m.is_castling() {
+            san.push_str(if m.dest().0 > m.src().0 { "O-O" } else { "O-O-O" });
+        } else if piece == Piece::Pawn {
+            if m.is_capturing() {
+                san.push(m.src().name().chars().next().unwrap());
+                san.push('x');
+            }
+            san.push_str(m.dest().name());
+            if let Some(promoted) = m.promoted() {
+                san.push('=');
+                san.push(piece_char(promoted));
+            }
+        } else {
+            san.push(piece_char(piece));
+            let others: Vec<ShortMove> = generate_legal_moves(self)
+                .into_iter()
+                .filter(|o| o.dest() == m.dest() && o.src() != m.src() && self.square(o.src()).map(|(_, p)| p) == Some(piece))
+                .collect();
+            if !others.is_empty() {
+                let same_file = others.iter().any(|o| o.src().file() == m.src().file());
+                let same_rank = others.iter().any(|o| o.src().rank() == m.src().rank());
+                let name = m.src().name();
+                if !same_file {
+                    san.push_str(&name[0..1]);
+                } else if !same_rank {
+                    san.push_str(&name[1..2]);
+                } else {
+                    san.push_str(name);
+                }
+            }
+            if m.is_capturing() {
+                san.push('x');
+            }
+            san.push_str(m.dest().name());
+        }
+        let mut next = self.clone();
+        next.make_move(m);
+        if next.in_check() {
+            san.push(if generate_legal_moves(&next).is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+}
+
+#[test]
+fn san_round_trip() {
+    let mut board = Board::new();
+    board.load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").expect("failed to load fen");
+    for m in generate_legal_moves(&board) {
+        let san = board.san(m);
+        assert_eq!(board.parse_san(&san).expect("failed to parse own SAN"), m, "{san}");
+    }
+    assert_eq!(board.parse_san("O-O-O").expect("failed to parse castling").to_string(), "e1c1");
+    assert_eq!(board.parse_san("Nxf7").expect("failed to parse capture").to_string(), "e5f7");
+}
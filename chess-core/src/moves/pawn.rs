@@ -1,4 +1,4 @@
-use crate::{board::{bitboard_square_iter, piece::Piece, rank::Rank, Board, EMPTY_BITBOARD}, PAWN_ATTACK_TABLE, PAWN_MOVE_TABLE};
+use crate::{board::{bitboard_square_iter, piece::{Piece, Side}, rank::Rank, Board, EMPTY_BITBOARD}, PAWN_ATTACK_TABLE, PAWN_MOVE_TABLE};
 
 use super::{ShortMove, MoveList};
 
@@ -7,7 +7,13 @@ impl MoveList {
         let active_side = board.active_side();
         let pawns = board.piece(active_side, Piece::Pawn);
         for from in bitboard_square_iter(pawns) {
-            let pushes = PAWN_MOVE_TABLE[active_side as usize][from.0 as usize] & !board.occupied();
+            let forward = PAWN_MOVE_TABLE[active_side as usize][from.0 as usize];
+            // A blocked square also blocks the double push behind it.
+            let blocked = match active_side {
+                Side::White => (forward & board.occupied()) << 8,
+                Side::Black => (forward & board.occupied()) >> 8,
+            };
+            let pushes = forward & !board.occupied() & !blocked;
             let en_passant = board.en_passant().map_or(EMPTY_BITBOARD, |sq| sq.as_mask());
             let enemy = board.side(active_side.other());
             let attacks = PAWN_ATTACK_TABLE[active_side as usize][from.0 as usize] & (en_passant | enemy);
@@ -1,4 +1,4 @@
-use crate::{board::{bitboard_square_iter, display_bitboard, piece::Piece, Board}, ROOK_ATTACK_TABLE, ROOK_MAGIC_TABLE};
+use crate::{board::{bitboard_square_iter, piece::Piece, Board}, ROOK_ATTACK_TABLE, ROOK_MAGIC_TABLE};
 
 use super::{ShortMove, MoveList};
 
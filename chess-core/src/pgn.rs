@@ -0,0 +1,167 @@
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+use crate::board::Board;
+
+/*
+* Reference: https://www.saremba.de/chessgml/standards/pgn/pgn-complete.htm
+*/
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWin,
+    BlackWin,
+    Draw,
+    /// Game in progress, abandoned or otherwise unknown ("*").
+    #[default]
+    Unknown,
+}
+
+impl GameResult {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWin),
+            "0-1" => Some(GameResult::BlackWin),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// A single game of a PGN file. Only the main line is kept, comments, variations and NAGs are dropped.
+#[derive(Debug, Default, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    /// Moves of the main line in standard algebraic notation.
+    pub moves: Vec<String>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Position the game starts from, given by the "FEN" tag or the standard starting position.
+    pub fn start(&self) -> anyhow::Result<Board> {
+        match self.tag("FEN") {
+            Some(fen) => {
+                let mut board = Board::new();
+                board.load_fen(fen)?;
+                Ok(board)
+            },
+            None => Ok(Board::default()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.moves.is_empty()
+    }
+}
+
+/// Parse every game in the PGN text. Parsing is lenient; malformed tags are skipped and moves are
+/// not checked for legality.
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = vec![];
+    let mut game = PgnGame::default();
+    let mut variation_depth = 0usize;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if variation_depth == 0 => {
+                if !game.moves.is_empty() {
+                    games.push(core::mem::take(&mut game));
+                }
+                let mut tag = String::new();
+                let mut quoted = false;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' if quoted => {
+                            if let Some(escaped) = chars.next() {
+                                tag.push(escaped);
+                            }
+                            continue;
+                        },
+                        '"' => quoted = !quoted,
+                        ']' if !quoted => break,
+                        _ => {},
+                    }
+                    tag.push(c);
+                }
+                if let Some((name, value)) = tag.trim().split_once(char::is_whitespace) {
+                    let value = value.trim();
+                    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+                    game.tags.push((name.to_string(), value.to_string()));
+                }
+            },
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            },
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            },
+            '(' => variation_depth += 1,
+            ')' => variation_depth = variation_depth.saturating_sub(1),
+            c if c.is_whitespace() => {},
+            c => {
+                let mut token = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || matches!(next, '{' | '}' | '(' | ')' | ';' | '[') {
+                        break;
+                    }
+                    token.push(next);
+                    chars.next();
+                }
+                if variation_depth > 0 || token.starts_with('$') {
+                    continue;
+                }
+                if let Some(result) = GameResult::parse(&token) {
+                    game.result = result;
+                    games.push(core::mem::take(&mut game));
+                    continue;
+                }
+                // Move numbers may be glued to the move, ie. "12.e4" or "12...Nf6".
+                let san = token.rsplit('.').next().unwrap_or_default();
+                if !san.is_empty() && !san.chars().all(|c| c.is_ascii_digit()) {
+                    game.moves.push(san.to_string());
+                }
+            },
+        }
+    }
+    if !game.is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+#[test]
+fn parse_pgn_main_line() {
+    let text = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2.Nf3 (2. f4 exf4) 2...Nc6 $1 3. Bb5 ; Ruy Lopez
+a6 1-0
+
+[Event "Second"]
+[FEN "8/8/8/8/8/8/4K3/4k3 w - - 0 1"]
+
+1. Kd3 Kf2 2. 0-0 1/2-1/2
+"#;
+    let games = parse_pgn(text);
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(games[0].moves, vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+    assert_eq!(games[0].result, GameResult::WhiteWin);
+    assert_eq!(games[1].moves, vec!["Kd3", "Kf2", "0-0"]);
+    assert_eq!(games[1].result, GameResult::Draw);
+    assert_eq!(games[1].start().expect("failed to load FEN tag").as_fen(), "8/8/8/8/8/8/4K3/4k3 w - - 0 1");
+}
@@ -0,0 +1,201 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! Human readable formats get the usual chess notations: [Board] as FEN and [Square] as "e4".
+//! [ShortMove] is stored as its raw `u16` in every format, since UCI text without a position loses
+//! the capture, castling and en passant flags. [GameHistory] has its position, so its moves are UCI
+//! text resolved against the board.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use core::{fmt::Display, str::FromStr};
+
+use serde::{de::{self, SeqAccess, Visitor}, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{board::{square::Square, Board}, game::GameHistory, moves::{MoveList, ShortMove, MAX_LEGAL_MOVES}};
+
+/// Reasons deserialization can reject its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerdeError {
+    InvalidFen { fen: String, reason: String },
+    InvalidSquare(String),
+    /// A raw move with the same source and destination.
+    InvalidMove(String),
+    /// A move of a [GameHistory] that is not legal in its position.
+    IllegalMove { ply: usize, notation: String },
+    TooManyMoves(usize),
+}
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SerdeError::InvalidFen { fen, reason } => write!(f, "invalid FEN \"{fen}\": {reason}"),
+            SerdeError::InvalidSquare(square) => write!(f, "invalid square \"{square}\""),
+            SerdeError::InvalidMove(notation) => write!(f, "invalid move \"{notation}\""),
+            SerdeError::IllegalMove { ply, notation } => write!(f, "illegal move \"{notation}\" at ply {ply}"),
+            SerdeError::TooManyMoves(count) => write!(f, "{count} moves do not fit in a move list of {MAX_LEGAL_MOVES}"),
+        }
+    }
+}
+
+impl core::error::Error for SerdeError {}
+
+fn parse_fen(fen: &str) -> Result<Board, SerdeError> {
+    let mut board = Board::new();
+    board.load_fen(fen).map_err(|e| SerdeError::InvalidFen { fen: fen.to_string(), reason: format!("{e:#}") })?;
+    Ok(board)
+}
+
+fn from_raw(raw: u16) -> Result<ShortMove, SerdeError> {
+    let m = ShortMove::from(raw);
+    if m.src() == m.dest() {
+        return Err(SerdeError::InvalidMove(format!("{raw:#06x}")));
+    }
+    Ok(m)
+}
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Square::from_str(&name).map_err(|_| de::Error::custom(SerdeError::InvalidSquare(name)))
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.as_fen())
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        parse_fen(&fen).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ShortMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ShortMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_raw(u16::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for MoveList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for m in self.iter() {
+            seq.serialize_element(m)?;
+        }
+        seq.end()
+    }
+}
+
+struct MoveListVisitor;
+
+impl<'de> Visitor<'de> for MoveListVisitor {
+    type Value = MoveList;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "a list of at most {MAX_LEGAL_MOVES} moves")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<MoveList, A::Error> {
+        let mut moves = MoveList::new();
+        while let Some(m) = seq.next_element::<ShortMove>()? {
+            if moves.len() == MAX_LEGAL_MOVES {
+                return Err(de::Error::custom(SerdeError::TooManyMoves(moves.len() + 1 + seq.size_hint().unwrap_or(0))));
+            }
+            moves.push(m);
+        }
+        Ok(moves)
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(MoveListVisitor)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GameHistoryRepr {
+    start: String,
+    moves: Vec<String>,
+}
+
+impl Serialize for GameHistory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameHistoryRepr {
+            start: self.start().as_fen(),
+            moves: self.moves().iter().map(ShortMove::to_string).collect(),
+        }.serialize(serializer)
+    }
+}
+
+/// Every move is replayed from the start position and must be legal.
+impl<'de> Deserialize<'de> for GameHistory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = GameHistoryRepr::deserialize(deserializer)?;
+        let start = parse_fen(&repr.start).map_err(de::Error::custom)?;
+        let mut board = start.clone();
+        let mut moves = Vec::with_capacity(repr.moves.len());
+        for (ply, notation) in repr.moves.into_iter().enumerate() {
+            let m = board.parse_uci_move(&notation)
+                .map_err(|_| de::Error::custom(SerdeError::IllegalMove { ply, notation }))?;
+            board.make_move(m);
+            moves.push(m);
+        }
+        Ok(GameHistory { start, moves })
+    }
+}
+
+#[test]
+fn serde_json_round_trip() {
+    let mut board = Board::new();
+    board.load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").expect("failed to load fen");
+    let json = serde_json::to_string(&board).unwrap();
+    assert_eq!(json, "\"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1\"");
+    assert_eq!(serde_json::from_str::<Board>(&json).unwrap().as_fen(), board.as_fen());
+    assert!(serde_json::from_str::<Board>("\"not a fen\"").err().unwrap().to_string().starts_with("invalid FEN"));
+
+    assert_eq!(serde_json::to_string(&Square::from_str("e4").unwrap()).unwrap(), "\"e4\"");
+    assert_eq!(serde_json::from_str::<Square>("\"e4\"").unwrap(), Square::from_str("e4").unwrap());
+    assert!(serde_json::from_str::<Square>("\"i9\"").is_err());
+
+    // Moves keep their flags, which UCI text alone can not tell.
+    let castling = board.parse_uci_move("e1g1").unwrap();
+    board.load_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").expect("failed to load fen");
+    let capture = board.parse_uci_move("e4d5").unwrap();
+    for m in [castling, capture] {
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(json, m.0.to_string());
+        let round_trip = serde_json::from_str::<ShortMove>(&json).unwrap();
+        assert_eq!(round_trip, m, "{m} should survive a round trip");
+        assert_eq!((round_trip.is_castling(), round_trip.is_capturing()), (m.is_castling(), m.is_capturing()));
+    }
+    assert!(serde_json::from_str::<ShortMove>(&ShortMove::new(Square::from_str("e1").unwrap(), Square::from_str("e1").unwrap(), 0).0.to_string()).is_err());
+    assert!(serde_json::from_str::<ShortMove>("\"e1g1\"").is_err());
+
+    board.load_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").expect("failed to load fen");
+    let moves = crate::moves::generate_legal_moves(&board);
+    let json = serde_json::to_string(&moves).unwrap();
+    let round_trip = serde_json::from_str::<MoveList>(&json).unwrap();
+    assert_eq!(round_trip.iter().collect::<Vec<_>>(), moves.iter().collect::<Vec<_>>());
+    assert!(moves.iter().any(ShortMove::is_en_pasant) && moves.iter().any(ShortMove::is_castling));
+
+    let history: GameHistory = serde_json::from_str(r#"{"start":"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1","moves":["e1g1","e8c8"]}"#).unwrap();
+    assert!(history.moves()[0].is_castling(), "moves should be resolved against the position");
+    assert_eq!(serde_json::to_string(&history).unwrap(), r#"{"start":"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1","moves":["e1g1","e8c8"]}"#);
+    let illegal = serde_json::from_str::<GameHistory>(r#"{"start":"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1","moves":["e1g1","e1g1"]}"#);
+    assert!(illegal.err().unwrap().to_string().starts_with("illegal move \"e1g1\" at ply 1"));
+}
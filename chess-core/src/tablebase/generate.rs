@@ -0,0 +1,200 @@
+use alloc::{format, vec, vec::Vec};
+
+use anyhow::{bail, Context};
+
+use crate::{board::{bitboard_square_iter, piece::{Piece, Side}, rank::Rank, square::Square, Bitboard, Board}, moves::{bishop_attacks, generate_legal_moves, rook_attacks}, zobrist, KING_MOVE_TABLE, KNIGHT_MOVE_TABLE};
+
+use super::{decode_value, index::Material, loss_value, win_value, Dtm, Table, Tablebase, DRAW, ILLEGAL};
+
+/*
+* Reference: https://www.chessprogramming.org/Retrograde_Analysis
+*/
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    /// The position was resolved at the bucket's distance and its predecessors should be updated.
+    Resolved(u32),
+    /// A capture or promotion from the position leads to a loss at the bucket's distance.
+    Win(u32),
+    /// A capture or promotion from the position leads to a win at the bucket's distance, so it
+    /// may have run out of moves that don't lose.
+    Check(u32),
+}
+
+struct Generator<'a> {
+    material: &'a Material,
+    tablebase: &'a Tablebase,
+    values: Vec<u8>,
+    /// Pending events by distance to mate in plies.
+    buckets: Vec<Vec<Event>>,
+}
+
+impl Generator<'_> {
+    fn push(&mut self, plies: usize, event: Event) {
+        if self.buckets.len() <= plies {
+            self.buckets.resize_with(plies + 1, Vec::new);
+        }
+        self.buckets[plies].push(event);
+    }
+
+    /// Value of the position after a move, looked up in the table under construction or, for
+    /// captures and promotions, in a smaller table.
+    fn child_value(&self, child: &Board, converted: bool) -> anyhow::Result<Option<Dtm>> {
+        if converted {
+            return self.tablebase.probe(child).map(Some).with_context(|| format!("missing table for {}", child.as_fen()));
+        }
+        Ok(decode_value(self.values[self.material.index(child)]))
+    }
+
+    fn seed(&mut self) -> anyhow::Result<()> {
+        for index in 0..self.material.size() {
+            let Some(board) = self.material.decode(index) else { continue };
+            self.values[index] = DRAW;
+            let moves = generate_legal_moves(&board);
+            if moves.is_empty() {
+                if board.in_check() {
+                    self.values[index] = loss_value(0)?;
+                    self.push(0, Event::Resolved(index as u32));
+                }
+                continue;
+            }
+            for m in moves {
+                if !m.is_capturing() && !m.is_promotion() {
+                    continue;
+                }
+                let mut child = board.clone();
+                child.make_move(m);
+                match self.child_value(&child, true)? {
+                    Some(Dtm::Loss(plies)) => self.push(plies as usize, Event::Win(index as u32)),
+                    Some(Dtm::Win(plies)) => self.push(plies as usize, Event::Check(index as u32)),
+                    _ => {},
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn win(&mut self, index: usize, plies: usize) -> anyhow::Result<()> {
+        if self.values[index] == DRAW {
+            self.values[index] = win_value(plies + 1)?;
+            self.push(plies + 1, Event::Resolved(index as u32));
+        }
+        Ok(())
+    }
+
+    /// Mark the position as lost if every move leads to a win for the opponent within the distance.
+    fn check(&mut self, index: usize, plies: usize) -> anyhow::Result<()> {
+        if self.values[index] != DRAW {
+            return Ok(());
+        }
+        let board = self.material.decode(index).expect("resolved positions should be legal");
+        for m in generate_legal_moves(&board) {
+            let mut child = board.clone();
+            child.make_move(m);
+            match self.child_value(&child, m.is_capturing() || m.is_promotion())? {
+                Some(Dtm::Win(child_plies)) if child_plies as usize <= plies => {},
+                _ => return Ok(()),
+            }
+        }
+        self.values[index] = loss_value(plies + 1)?;
+        self.push(plies + 1, Event::Resolved(index as u32));
+        Ok(())
+    }
+
+    fn run(&mut self) -> anyhow::Result<()> {
+        let mut plies = 0;
+        while plies < self.buckets.len() {
+            for event in core::mem::take(&mut self.buckets[plies]) {
+                match event {
+                    Event::Resolved(index) => {
+                        let board = self.material.decode(index as usize).expect("resolved positions should be legal");
+                        let lost = matches!(decode_value(self.values[index as usize]), Some(Dtm::Loss(_)));
+                        for predecessor in predecessors(&board) {
+                            let predecessor = self.material.index(&predecessor);
+                            if lost {
+                                self.win(predecessor, plies)?;
+                            } else {
+                                self.check(predecessor, plies)?;
+                            }
+                        }
+                    },
+                    Event::Win(index) => self.win(index as usize, plies)?,
+                    Event::Check(index) => self.check(index as usize, plies)?,
+                }
+            }
+            plies += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Squares a piece could have moved from to reach the square, without capturing.
+fn unmove_sources(side: Side, piece: Piece, to: Square, occupied: Bitboard) -> Bitboard {
+    let sq = to.0 as usize;
+    let sources = match piece {
+        Piece::King => KING_MOVE_TABLE[sq],
+        Piece::Knight => KNIGHT_MOVE_TABLE[sq],
+        Piece::Bishop => bishop_attacks(to, occupied),
+        Piece::Rook => rook_attacks(to, occupied),
+        Piece::Queen => bishop_attacks(to, occupied) | rook_attacks(to, occupied),
+        Piece::Pawn => {
+            let (single, double, push_rank, double_rank) = match side {
+                Side::White => (sq.wrapping_sub(8), sq.wrapping_sub(16), Rank::R3, Rank::R4),
+                Side::Black => (sq + 8, sq + 16, Rank::R6, Rank::R5),
+            };
+            let reached = to.rank();
+            let on_push_ranks = match side {
+                Side::White => reached >= push_rank,
+                Side::Black => reached <= push_rank,
+            };
+            let mut sources = 0;
+            if on_push_ranks && occupied & (1 << single) == 0 {
+                sources |= 1 << single;
+                if reached == double_rank && occupied & (1 << double) == 0 {
+                    sources |= 1 << double;
+                }
+            }
+            sources
+        },
+    };
+    sources & !occupied
+}
+
+/// Legal positions the board can be reached from by a quiet move of the side not to move.
+fn predecessors(board: &Board) -> Vec<Board> {
+    let mover = board.active_side().other();
+    let occupied = board.occupied();
+    let mut result = vec![];
+    for piece in [Piece::King, Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight, Piece::Pawn] {
+        for to in bitboard_square_iter(board.piece(mover, piece)) {
+            for from in bitboard_square_iter(unmove_sources(mover, piece, to, occupied)) {
+                let mut previous = board.clone();
+                previous.move_piece(mover, piece, to, from);
+                previous.active_side = mover;
+                previous.en_passant = None;
+                previous.hash ^= zobrist::turn_key();
+                if !previous.is_attacked(previous.king_square(mover.other()), mover) {
+                    result.push(previous);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Solve every position of the material by retrograde analysis. Tables for the materials reachable
+/// by captures and promotions must already be in the tablebase. En passant rights are ignored.
+pub(crate) fn generate_table(material: &Material, tablebase: &Tablebase) -> anyhow::Result<Table> {
+    if material.num_pieces() > super::MAX_TABLEBASE_PIECES {
+        bail!("material {material} has too many pieces");
+    }
+    let mut generator = Generator {
+        material,
+        tablebase,
+        values: vec![ILLEGAL; material.size()],
+        buckets: vec![],
+    };
+    generator.seed()?;
+    generator.run()?;
+    Ok(Table { material: material.clone(), values: generator.values })
+}
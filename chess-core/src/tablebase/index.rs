@@ -0,0 +1,222 @@
+use alloc::{format, vec, vec::Vec};
+use core::fmt::Display;
+
+use anyhow::{bail, Context};
+
+use crate::board::{bitboard_square_iter, piece::{Piece, Side}, rank::Rank, square::Square, Board};
+
+/// Largest number of pieces, kings included, that a table can be generated for.
+pub const MAX_TABLEBASE_PIECES: usize = 4;
+/// White king squares once mirrored into the a1-d4 quadrant.
+const PAWNLESS_KING_SQUARES: usize = 16;
+/// White king squares once mirrored onto the a-d files. Pawns prevent mirroring the ranks.
+const PAWN_KING_SQUARES: usize = 32;
+
+fn piece_char(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+/// Material signature of a table, ie. "KRKP" for king and rook against king and pawn.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Material {
+    /// Pieces besides the king, strongest first.
+    white: Vec<Piece>,
+    black: Vec<Piece>,
+}
+
+impl Material {
+    pub fn parse(signature: &str) -> anyhow::Result<Self> {
+        let rest = signature.strip_prefix('K').with_context(|| format!("signature must start with the white king: {signature}"))?;
+        let (white, black) = rest.split_once('K').with_context(|| format!("signature is missing the black king: {signature}"))?;
+        let parse_side = |pieces: &str| pieces.chars().map(|c| match c {
+            'Q' => Ok(Piece::Queen),
+            'R' => Ok(Piece::Rook),
+            'B' => Ok(Piece::Bishop),
+            'N' => Ok(Piece::Knight),
+            'P' => Ok(Piece::Pawn),
+            _ => bail!("invalid piece in signature {signature}: {c}"),
+        }).collect::<anyhow::Result<Vec<Piece>>>();
+        let material = Self::new(parse_side(white)?, parse_side(black)?);
+        if material.num_pieces() > MAX_TABLEBASE_PIECES {
+            bail!("signature {signature} has more than {MAX_TABLEBASE_PIECES} pieces");
+        }
+        Ok(material)
+    }
+
+    fn new(mut white: Vec<Piece>, mut black: Vec<Piece>) -> Self {
+        white.sort_by_key(|piece| core::cmp::Reverse(*piece as u8));
+        black.sort_by_key(|piece| core::cmp::Reverse(*piece as u8));
+        Self { white, black }
+    }
+
+    /// Material on the board, or [None] if a king is missing.
+    pub fn of(board: &Board) -> Option<Self> {
+        if board.piece(Side::White, Piece::King).count_ones() != 1 || board.piece(Side::Black, Piece::King).count_ones() != 1 {
+            return None;
+        }
+        let side_pieces = |side: Side| [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight, Piece::Pawn].into_iter()
+            .flat_map(|piece| core::iter::repeat_n(piece, board.piece(side, piece).count_ones() as usize))
+            .collect::<Vec<Piece>>();
+        Some(Self::new(side_pieces(Side::White), side_pieces(Side::Black)))
+    }
+
+    /// The same material with the sides swapped.
+    pub fn mirrored(&self) -> Self {
+        Self { white: self.black.clone(), black: self.white.clone() }
+    }
+
+    pub fn num_pieces(&self) -> usize {
+        2 + self.white.len() + self.black.len()
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.white.contains(&Piece::Pawn) || self.black.contains(&Piece::Pawn)
+    }
+
+    /// Materials reachable from this one by a capture or a promotion.
+    pub(crate) fn conversions(&self) -> Vec<Self> {
+        let mut result = vec![];
+        for (side, pieces) in [(Side::White, &self.white), (Side::Black, &self.black)] {
+            for (n, piece) in pieces.iter().enumerate() {
+                let mut replacements = vec![None];
+                if *piece == Piece::Pawn {
+                    replacements.extend([Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen].map(Some));
+                }
+                for replacement in replacements {
+                    let mut changed = pieces.clone();
+                    changed.remove(n);
+                    changed.extend(replacement);
+                    let material = match side {
+                        Side::White => Self::new(changed, self.black.clone()),
+                        Side::Black => Self::new(self.white.clone(), changed),
+                    };
+                    if !result.contains(&material) {
+                        result.push(material);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Pieces besides the kings in index order.
+    pub(crate) fn others(&self) -> impl Iterator<Item = (Side, Piece)> + '_ {
+        self.white.iter().map(|piece| (Side::White, *piece))
+            .chain(self.black.iter().map(|piece| (Side::Black, *piece)))
+    }
+
+    fn king_squares(&self) -> usize {
+        if self.has_pawns() { PAWN_KING_SQUARES } else { PAWNLESS_KING_SQUARES }
+    }
+
+    /// Number of entries in the table, including unreachable and illegal ones.
+    pub(crate) fn size(&self) -> usize {
+        2 * self.king_squares() * 64usize.pow(1 + (self.num_pieces() - 2) as u32)
+    }
+
+    /// Index of a board with this material. The board is mirrored so that the white king is on
+    /// the a-d files, and also on ranks 1-4 when there are no pawns.
+    pub(crate) fn index(&self, board: &Board) -> usize {
+        let white_king = board.king_square(Side::White);
+        let flip_file = white_king.file() as u8 >= 4;
+        let flip_rank = !self.has_pawns() && white_king.rank() >= Rank::R5;
+        let transform = |square: Square| {
+            let square = if flip_file { square.flip_file() } else { square };
+            if flip_rank { square.flip_rank() } else { square }
+        };
+        let white_king = transform(white_king);
+        let mut index = board.active_side() as usize;
+        index = (index * self.king_squares()) + (white_king.0 as usize / 8 * 4) + (white_king.0 as usize % 8);
+        index = (index * 64) + transform(board.king_square(Side::Black)).0 as usize;
+        let mut last = None;
+        for (side, piece) in self.others() {
+            if last == Some((side, piece)) {
+                continue;
+            }
+            last = Some((side, piece));
+            let mut squares: Vec<u8> = bitboard_square_iter(board.piece(side, piece)).map(|square| transform(square).0).collect();
+            squares.sort_unstable();
+            for square in squares {
+                index = (index * 64) + square as usize;
+            }
+        }
+        index
+    }
+
+    /// Board for the index, or [None] if the placement is illegal, not in its canonical order, or
+    /// the side not to move is in check.
+    pub(crate) fn decode(&self, index: usize) -> Option<Board> {
+        let others: Vec<(Side, Piece)> = self.others().collect();
+        let mut squares = vec![0u8; others.len()];
+        let mut rest = index;
+        for square in squares.iter_mut().rev() {
+            *square = (rest % 64) as u8;
+            rest /= 64;
+        }
+        let black_king = Square((rest % 64) as u8);
+        rest /= 64;
+        let king_index = rest % self.king_squares();
+        let white_king = Square(((king_index / 4 * 8) + (king_index % 4)) as u8);
+        let active_side = Side::from(rest / self.king_squares());
+
+        let mut board = Board::new();
+        board.place_piece(Side::White, Piece::King, white_king);
+        if board.occupied() & black_king.as_mask() != 0 {
+            return None;
+        }
+        board.place_piece(Side::Black, Piece::King, black_king);
+        for (n, (side, piece)) in others.iter().enumerate() {
+            let square = Square(squares[n]);
+            if board.occupied() & square.as_mask() != 0 {
+                return None;
+            }
+            if *piece == Piece::Pawn && (square.rank() == Rank::R1 || square.rank() == Rank::R8) {
+                return None;
+            }
+            // Identical pieces are only indexed in ascending square order.
+            if n > 0 && others[n - 1] == (*side, *piece) && squares[n - 1] > squares[n] {
+                return None;
+            }
+            board.place_piece(*side, *piece, square);
+        }
+        board.active_side = active_side;
+        board.hash = board.compute_hash();
+        if board.is_attacked(board.king_square(active_side.other()), active_side) {
+            return None;
+        }
+        Some(board)
+    }
+}
+
+impl Display for Material {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "K")?;
+        for piece in self.white.iter() {
+            write!(f, "{}", piece_char(*piece))?;
+        }
+        write!(f, "K")?;
+        for piece in self.black.iter() {
+            write!(f, "{}", piece_char(*piece))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn index_round_trip() {
+    let material = Material::parse("KRKP").expect("failed to parse signature");
+    assert_eq!(material.to_string(), "KRKP");
+    let mut board = Board::new();
+    board.load_fen("8/8/8/3k4/8/1p6/6KR/8 b - - 0 1").expect("failed to load fen");
+    let index = material.index(&board);
+    let decoded = material.decode(index).expect("index should decode");
+    assert_eq!(decoded.as_fen(), "8/8/8/4k3/8/6p1/RK6/8 b - - 0 1", "white king should be mirrored onto the b file");
+    assert_eq!(material.index(&decoded), index);
+}
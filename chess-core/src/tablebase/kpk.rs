@@ -0,0 +1,46 @@
+// The build script generates the bitbase with the same index.
+use crate::{board::{bitboard_square_iter, piece::{Piece, Side}, square::Square, Board}, tables::kpk::kpk_index, KPK_BITBASE};
+
+/// Look up a king and pawn versus king position in the built-in bitbase. Returns whether the side
+/// with the pawn wins, or [None] for any other material.
+pub fn kpk_probe(board: &Board) -> Option<bool> {
+    let pawns = board.piece(Side::White, Piece::Pawn) | board.piece(Side::Black, Piece::Pawn);
+    if pawns.count_ones() != 1 || board.occupied().count_ones() != 3 {
+        return None;
+    }
+    if board.piece(Side::White, Piece::King).count_ones() != 1 || board.piece(Side::Black, Piece::King).count_ones() != 1 {
+        return None;
+    }
+    let board = if board.piece(Side::Black, Piece::Pawn) != 0 { &board.mirrored() } else { board };
+    let pawn = bitboard_square_iter(board.piece(Side::White, Piece::Pawn)).next()?;
+    let flip = pawn.0 % 8 >= 4;
+    let square = |square: Square| (if flip { square.flip_file() } else { square }).0 as usize;
+    let pawn = square(pawn);
+    if !(8..56).contains(&pawn) {
+        return None;
+    }
+    let index = kpk_index(board.active_side(), square(board.king_square(Side::White)), square(board.king_square(Side::Black)), pawn);
+    Some(KPK_BITBASE[index / 64] & (1 << (index % 64)) != 0)
+}
+
+#[test]
+fn kpk_matches_retrograde_tables() {
+    use super::{Dtm, Tablebase, Material};
+
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KPK").expect("failed to generate KPK");
+    let material = Material::parse("KPK").unwrap();
+    // Sample every 7th index so both sides to move and every pawn square are covered.
+    let mut checked = 0;
+    for index in (0..material.size()).step_by(7) {
+        let Some(board) = material.decode(index) else { continue };
+        let expected = tablebase.probe(&board).map(|dtm| match board.active_side() {
+            Side::White => matches!(dtm, Dtm::Win(_)),
+            Side::Black => matches!(dtm, Dtm::Loss(_)),
+        });
+        assert_eq!(kpk_probe(&board), expected, "{}", board.as_fen());
+        assert_eq!(kpk_probe(&board.mirrored()), expected, "{}", board.mirrored().as_fen());
+        checked += 1;
+    }
+    assert!(checked > 10_000);
+}
@@ -0,0 +1,301 @@
+//! Endgame tablebases for small material, generated locally by retrograde analysis.
+
+use alloc::{collections::BTreeMap, string::{String, ToString}, vec::Vec};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use anyhow::{bail, Context};
+
+use crate::board::Board;
+
+pub use index::{Material, MAX_TABLEBASE_PIECES};
+pub use kpk::kpk_probe;
+
+mod generate;
+mod index;
+mod kpk;
+
+/// Extension of the table files written by [Tablebase::save_dir].
+pub const TABLE_EXTENSION: &str = "mctb";
+const TABLE_MAGIC: &[u8; 4] = b"MCTB";
+const TABLE_VERSION: u8 = 1;
+
+/*
+* Each entry is a single byte: 0 for a draw, 1-127 for a win in that many moves, 128 plus the number
+* of moves for a loss, and 255 for illegal positions.
+*/
+pub(crate) const DRAW: u8 = 0;
+pub(crate) const ILLEGAL: u8 = 255;
+const LOSS_OFFSET: u8 = 128;
+
+/// Distance to mate in plies from the perspective of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dtm {
+    Win(u16),
+    Draw,
+    Loss(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Dtm {
+    pub fn wdl(&self) -> Wdl {
+        match self {
+            Dtm::Win(_) => Wdl::Win,
+            Dtm::Draw => Wdl::Draw,
+            Dtm::Loss(_) => Wdl::Loss,
+        }
+    }
+}
+
+pub(crate) fn win_value(plies: usize) -> anyhow::Result<u8> {
+    let moves = plies.div_ceil(2);
+    if moves >= LOSS_OFFSET as usize {
+        bail!("mate in {moves} is too long to store");
+    }
+    Ok(moves as u8)
+}
+
+pub(crate) fn loss_value(plies: usize) -> anyhow::Result<u8> {
+    let moves = plies / 2;
+    if moves >= (ILLEGAL - LOSS_OFFSET) as usize {
+        bail!("mated in {moves} is too long to store");
+    }
+    Ok(LOSS_OFFSET + moves as u8)
+}
+
+/// Value of a table entry, or [None] for an illegal position.
+pub(crate) fn decode_value(value: u8) -> Option<Dtm> {
+    match value {
+        DRAW => Some(Dtm::Draw),
+        ILLEGAL => None,
+        v if v < LOSS_OFFSET => Some(Dtm::Win(v as u16 * 2 - 1)),
+        v => Some(Dtm::Loss((v - LOSS_OFFSET) as u16 * 2)),
+    }
+}
+
+/// Solved positions of a single material signature.
+#[derive(Debug, Clone)]
+pub(crate) struct Table {
+    material: Material,
+    values: Vec<u8>,
+}
+
+impl Table {
+    /// Serialize the table with run-length encoding, as most entries are draws or illegal.
+    fn to_bytes(&self) -> Vec<u8> {
+        let signature = self.material.to_string();
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(TABLE_MAGIC);
+        bytes.push(TABLE_VERSION);
+        bytes.push(signature.len() as u8);
+        bytes.extend_from_slice(signature.as_bytes());
+        bytes.extend_from_slice(&(self.values.len() as u64).to_le_bytes());
+        let mut values = self.values.iter().peekable();
+        while let Some(&value) = values.next() {
+            let mut run = 1u64;
+            while values.next_if_eq(&&value).is_some() {
+                run += 1;
+            }
+            bytes.push(value);
+            // Run length as LEB128.
+            loop {
+                let byte = (run & 0x7F) as u8;
+                run >>= 7;
+                if run == 0 {
+                    bytes.push(byte);
+                    break;
+                }
+                bytes.push(byte | 0x80);
+            }
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < 6 || &bytes[0..4] != TABLE_MAGIC {
+            bail!("not a tablebase file");
+        }
+        if bytes[4] != TABLE_VERSION {
+            bail!("unsupported tablebase version {}", bytes[4]);
+        }
+        let signature_end = 6 + bytes[5] as usize;
+        let signature = bytes.get(6..signature_end).and_then(|s| core::str::from_utf8(s).ok()).context("invalid table signature")?;
+        let material = Material::parse(signature)?;
+        let count = bytes.get(signature_end..signature_end + 8).context("truncated table header")?;
+        let count = u64::from_le_bytes(count.try_into().unwrap()) as usize;
+        if count != material.size() {
+            bail!("table {material} has {count} entries, expected {}", material.size());
+        }
+
+        let mut values = Vec::with_capacity(count);
+        let mut rest = bytes[signature_end + 8..].iter();
+        while let Some(&value) = rest.next() {
+            let mut run = 0u64;
+            let mut shift = 0;
+            loop {
+                let byte = *rest.next().context("truncated table run")?;
+                run |= ((byte & 0x7F) as u64) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+                if shift >= 64 {
+                    bail!("invalid table run length");
+                }
+            }
+            if values.len() + run as usize > count {
+                bail!("table {material} has too many entries");
+            }
+            values.resize(values.len() + run as usize, value);
+        }
+        if values.len() != count {
+            bail!("table {material} is truncated");
+        }
+        Ok(Self { material, values })
+    }
+}
+
+/// A set of generated tables, probed with [Board::probe_tablebase].
+#[derive(Debug, Default, Clone)]
+pub struct Tablebase {
+    tables: BTreeMap<Material, Table>,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate the table for a signature such as "KRKP", and every smaller table it converts into.
+    /// Tables already present, including ones with the colors swapped, are kept.
+    pub fn generate(&mut self, signature: &str) -> anyhow::Result<()> {
+        self.generate_material(&Material::parse(signature)?)
+    }
+
+    fn generate_material(&mut self, material: &Material) -> anyhow::Result<()> {
+        if self.contains(material) {
+            return Ok(());
+        }
+        for conversion in material.conversions() {
+            self.generate_material(&conversion)?;
+        }
+        let table = generate::generate_table(material, self)?;
+        self.tables.insert(material.clone(), table);
+        Ok(())
+    }
+
+    fn contains(&self, material: &Material) -> bool {
+        self.tables.contains_key(material) || self.tables.contains_key(&material.mirrored())
+    }
+
+    /// Signatures of the tables in the tablebase, sorted.
+    pub fn signatures(&self) -> Vec<String> {
+        let mut signatures: Vec<String> = self.tables.keys().map(Material::to_string).collect();
+        signatures.sort();
+        signatures
+    }
+
+    /// Distance to mate of the position, or [None] if there is no table for it, castling is still
+    /// possible or the position is illegal. En passant rights are ignored.
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        if board.castling() != 0 {
+            return None;
+        }
+        let material = Material::of(board)?;
+        if let Some(table) = self.tables.get(&material) {
+            return decode_value(table.values[material.index(board)]);
+        }
+        let table = self.tables.get(&material.mirrored())?;
+        decode_value(table.values[table.material.index(&board.mirrored())])
+    }
+
+    /// Add a table from the contents of a table file, replacing any table for the same signature.
+    pub fn load_table(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        let table = Table::from_bytes(bytes)?;
+        self.tables.insert(table.material.clone(), table);
+        Ok(())
+    }
+
+    /// Contents of the table file for the signature, as written by [Tablebase::save_dir].
+    pub fn table_bytes(&self, signature: &str) -> Option<Vec<u8>> {
+        self.tables.get(&Material::parse(signature).ok()?).map(Table::to_bytes)
+    }
+
+    /// Write every table to `<signature>.mctb` in the directory.
+    #[cfg(feature = "std")]
+    pub fn save_dir(&self, dir: impl AsRef<Path>) -> anyhow::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        for table in self.tables.values() {
+            let path = dir.join(format!("{}.{TABLE_EXTENSION}", table.material));
+            std::fs::write(&path, table.to_bytes()).with_context(|| format!("failed to write table {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Load every table file in the directory.
+    #[cfg(feature = "std")]
+    pub fn load_dir(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        let mut tablebase = Self::new();
+        for entry in std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != TABLE_EXTENSION) {
+                continue;
+            }
+            let bytes = std::fs::read(&path).with_context(|| format!("failed to read table {}", path.display()))?;
+            tablebase.load_table(&bytes).with_context(|| format!("invalid table {}", path.display()))?;
+        }
+        Ok(tablebase)
+    }
+}
+
+impl Board {
+    /// Distance to mate of the position according to the tablebase, see [Tablebase::probe].
+    pub fn probe_tablebase(&self, tablebase: &Tablebase) -> Option<Dtm> {
+        tablebase.probe(self)
+    }
+}
+
+#[cfg(test)]
+fn longest_win(tablebase: &Tablebase, signature: &str) -> u16 {
+    let material = Material::parse(signature).unwrap();
+    tablebase.tables[&material].values.iter()
+        .filter_map(|value| match decode_value(*value) {
+            Some(Dtm::Win(plies)) => Some(plies),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn generate_three_piece_tables() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KQK").expect("failed to generate KQK");
+    tablebase.generate("KRK").expect("failed to generate KRK");
+    assert_eq!(longest_win(&tablebase, "KQK"), 19, "longest KQK mate is 10 moves");
+    assert_eq!(longest_win(&tablebase, "KRK"), 31, "longest KRK mate is 16 moves");
+
+    let probe = |fen: &str| {
+        let mut board = Board::new();
+        board.load_fen(fen).expect("failed to load fen");
+        board.probe_tablebase(&tablebase)
+    };
+    assert_eq!(probe("k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(probe("R6k/8/6K1/8/8/8/8/8 b - - 0 1"), Some(Dtm::Loss(0)));
+    assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Draw), "stalemate");
+    assert_eq!(probe("8/8/8/8/8/1k6/1q6/K7 w - - 0 1"), Some(Dtm::Loss(0)), "colors should be mirrored");
+    assert_eq!(probe("k6q/8/8/8/8/8/8/K6R w - - 0 1"), None, "KRKQ was not generated");
+
+    let mut reloaded = Tablebase::new();
+    reloaded.load_table(&tablebase.table_bytes("KRK").expect("KRK was generated")).expect("failed to reload table");
+    let material = Material::parse("KRK").unwrap();
+    assert_eq!(reloaded.tables[&material].values, tablebase.tables[&material].values);
+}
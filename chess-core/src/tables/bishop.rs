@@ -44,11 +44,15 @@ pub fn gen_bishop_magics() -> (Vec<Bitboard>, [Magic; NUM_BOARD_SQUARES]) {
             let mut ld = square.as_mask();
             let mut rd = square.as_mask();
             for _ in 0..8 {
-                lu = (lu << 7) & !FILE_H & !blocker;
-                ru = (ru << 9) & !FILE_A & !blocker;
-                ld = (ld >> 7) & !FILE_H & !blocker;
-                rd = (rd >> 9) & !FILE_A & !blocker;
+                lu = (lu << 7) & !FILE_H;
+                ru = (ru << 9) & !FILE_A;
+                ld = (ld >> 9) & !FILE_H;
+                rd = (rd >> 7) & !FILE_A;
                 attack |= lu | ru | ld | rd;
+                lu &= !blocker;
+                ru &= !blocker;
+                ld &= !blocker;
+                rd &= !blocker;
             }
             attack
         }).collect();
@@ -56,8 +60,8 @@ pub fn gen_bishop_magics() -> (Vec<Bitboard>, [Magic; NUM_BOARD_SQUARES]) {
         for next in 0..permutations {
             let index = magic.as_index(blockers[next as usize]);
             if table[index] == EMPTY_BITBOARD {
-                assert!(!(index < offset as usize), "magic index is short of the offset");
-                assert!(!(index > (offset + permutations - 1) as usize), "magic index excedes end");
+                assert!(index >= offset as usize, "magic index is short of the offset");
+                assert!(index <= (offset + permutations - 1) as usize, "magic index excedes end");
                 table[index] = attacks[next as usize]
             } else {
                 panic!("bishop magic table index already occupied:\nindex: {}\nold:\n{}\nnew:\n{}",
@@ -0,0 +1,134 @@
+use alloc::{vec, vec::Vec};
+
+use crate::board::{piece::Side, square::NUM_BOARD_SQUARES, Bitboard};
+
+use super::{king::gen_king_moves, pawn::gen_pawn_attacks};
+
+/*
+* Reference: https://www.chessprogramming.org/KPK
+*
+* Positions are indexed with White holding the pawn on the a-d files, see `tablebase::kpk`.
+*/
+
+/// Pawn squares on the a-d files of ranks 2-7.
+pub const KPK_PAWN_SQUARES: usize = 24;
+/// Number of positions in the bitbase, one bit each.
+pub const KPK_SIZE: usize = 2 * KPK_PAWN_SQUARES * NUM_BOARD_SQUARES * NUM_BOARD_SQUARES;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+pub fn kpk_index(side: Side, white_king: usize, black_king: usize, pawn: usize) -> usize {
+    let pawn_index = (pawn / 8 - 1) * 4 + pawn % 8;
+    (((side as usize * KPK_PAWN_SQUARES) + pawn_index) * NUM_BOARD_SQUARES + white_king) * NUM_BOARD_SQUARES + black_king
+}
+
+fn distance(a: usize, b: usize) -> usize {
+    (a / 8).abs_diff(b / 8).max((a % 8).abs_diff(b % 8))
+}
+
+struct Position {
+    side: Side,
+    white_king: usize,
+    black_king: usize,
+    pawn: usize,
+}
+
+impl Position {
+    fn decode(index: usize) -> Self {
+        let black_king = index % NUM_BOARD_SQUARES;
+        let white_king = (index / NUM_BOARD_SQUARES) % NUM_BOARD_SQUARES;
+        let rest = index / (NUM_BOARD_SQUARES * NUM_BOARD_SQUARES);
+        let pawn_index = rest % KPK_PAWN_SQUARES;
+        Self {
+            side: Side::from(rest / KPK_PAWN_SQUARES),
+            white_king,
+            black_king,
+            pawn: (pawn_index / 4 + 1) * 8 + pawn_index % 4,
+        }
+    }
+}
+
+/// Classify every KPK position as a white win or a draw by iterating until nothing changes.
+/// Returns the wins packed as bits by [kpk_index].
+pub fn gen_kpk_bitbase() -> Vec<u64> {
+    let king_moves: [Bitboard; NUM_BOARD_SQUARES] = gen_king_moves();
+    let pawn_attacks = gen_pawn_attacks()[Side::White as usize];
+    let mut results = vec![INVALID; KPK_SIZE];
+
+    for (index, result) in results.iter_mut().enumerate() {
+        let Position { side, white_king, black_king, pawn } = Position::decode(index);
+        let push = pawn + 8;
+        *result = if distance(white_king, black_king) <= 1 || white_king == pawn || black_king == pawn
+            || (side == Side::White && pawn_attacks[pawn] & (1 << black_king) != 0) {
+            INVALID
+        } else if side == Side::White && pawn / 8 == 6 && white_king != push && black_king != push
+            && (distance(black_king, push) > 1 || distance(white_king, push) == 1) {
+            // The pawn promotes and the queen can't be taken.
+            WIN
+        } else if side == Side::Black && (king_moves[black_king] & !(king_moves[white_king] | pawn_attacks[pawn]) == 0
+            || (distance(black_king, pawn) == 1 && distance(white_king, pawn) > 1)) {
+            // Stalemate, or the undefended pawn is taken.
+            DRAW
+        } else {
+            UNKNOWN
+        };
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..KPK_SIZE {
+            if results[index] != UNKNOWN {
+                continue;
+            }
+            let Position { side, white_king, black_king, pawn } = Position::decode(index);
+            let mut reached = INVALID;
+            let (good, bad) = match side {
+                Side::White => {
+                    for to in 0..NUM_BOARD_SQUARES {
+                        if king_moves[white_king] & (1 << to) != 0 {
+                            reached |= results[kpk_index(Side::Black, to, black_king, pawn)];
+                        }
+                    }
+                    if pawn / 8 < 6 {
+                        reached |= results[kpk_index(Side::Black, white_king, black_king, pawn + 8)];
+                    }
+                    if pawn / 8 == 1 && pawn + 8 != white_king && pawn + 8 != black_king {
+                        reached |= results[kpk_index(Side::Black, white_king, black_king, pawn + 16)];
+                    }
+                    (WIN, DRAW)
+                },
+                Side::Black => {
+                    for to in 0..NUM_BOARD_SQUARES {
+                        if king_moves[black_king] & (1 << to) != 0 {
+                            reached |= results[kpk_index(Side::White, white_king, to, pawn)];
+                        }
+                    }
+                    (DRAW, WIN)
+                },
+            };
+            let result = if reached & good != 0 {
+                good
+            } else if reached & UNKNOWN != 0 {
+                UNKNOWN
+            } else {
+                bad
+            };
+            if result != UNKNOWN {
+                results[index] = result;
+                changed = true;
+            }
+        }
+    }
+
+    let mut bitbase = vec![0u64; KPK_SIZE / 64];
+    for (index, result) in results.iter().enumerate() {
+        if *result == WIN {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bitbase
+}
@@ -1,6 +1,7 @@
 pub mod bishop;
 pub mod king;
 pub mod knight;
+pub mod kpk;
 pub mod pawn;
 pub mod queen;
 pub mod rook;
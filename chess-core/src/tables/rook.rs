@@ -33,8 +33,8 @@ pub fn gen_rook_magics() -> (Vec<Bitboard>, [Magic; NUM_BOARD_SQUARES]) {
             let mut left = square.as_mask();
             let mut right = square.as_mask();
             for _ in 0..NUM_BOARD_FILES {
-                left = (left << 1) & !FILE_H;
-                right = (right >> 1) & !FILE_A;
+                left = (left >> 1) & !FILE_H;
+                right = (right << 1) & !FILE_A;
                 attack |= left | right;
                 left &= !blocker;
                 right &= !blocker;
@@ -42,8 +42,8 @@ pub fn gen_rook_magics() -> (Vec<Bitboard>, [Magic; NUM_BOARD_SQUARES]) {
             let mut up = square.as_mask();
             let mut down = square.as_mask();
             for _ in 0..NUM_BOARD_RANKS {
-                up = (up << 8) & !RANK_8;
-                down = (down >> 8) & !RANK_1;
+                up <<= 8;
+                down >>= 8;
                 attack |= up | down;
                 up &= !blocker;
                 down &= !blocker;
@@ -54,8 +54,8 @@ pub fn gen_rook_magics() -> (Vec<Bitboard>, [Magic; NUM_BOARD_SQUARES]) {
         for next in 0..permutations {
             let index = magic.as_index(blockers[next as usize]);
             if table[index] == EMPTY_BITBOARD {
-                assert!(!(index < offset as usize), "magic index is short of the offset");
-                assert!(!(index > (offset + permutations - 1) as usize), "magic index excedes end");
+                assert!(index >= offset as usize, "magic index is short of the offset");
+                assert!(index <= (offset + permutations - 1) as usize, "magic index excedes end");
                 table[index] = attacks[next as usize]
             } else {
                 panic!("rook magic table index already occupied:\nindex: {}\nold:\n{}\nnew:\n{}",
@@ -0,0 +1,260 @@
+use crate::board::{piece::{Piece, Side}, square::Square};
+
+/*
+* Reference: http://hgm.nubati.net/book_format.html
+*/
+
+/// Number of keys in the Polyglot random array.
+pub const NUM_POLYGLOT_KEYS: usize = 781;
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+/// Key for a piece standing on a square.
+pub fn piece_key(side: Side, piece: Piece, square: Square) -> u64 {
+    // Polyglot orders black before white for every kind of piece.
+    let kind = (piece as usize * 2) + (side.other() as usize);
+    POLYGLOT_RANDOM[(64 * kind) + square.0 as usize]
+}
+
+/// Key for a single castling right. Expects one of the castling right bits used by [crate::board::Board].
+pub fn castling_key(right: u8) -> u64 {
+    debug_assert!(right.count_ones() == 1, "expected a single castling right");
+    POLYGLOT_RANDOM[CASTLING_OFFSET + right.trailing_zeros() as usize]
+}
+
+/// Key for all castling rights in the given mask.
+pub fn castling_keys(rights: u8) -> u64 {
+    (0..4).filter(|n| rights & (1 << n) != 0).fold(0, |key, n| key ^ POLYGLOT_RANDOM[CASTLING_OFFSET + n])
+}
+
+/// Key for an en passant file.
+pub fn en_passant_key(file: usize) -> u64 {
+    POLYGLOT_RANDOM[EN_PASSANT_OFFSET + file]
+}
+
+/// Key for white being the active side.
+pub fn turn_key() -> u64 {
+    POLYGLOT_RANDOM[TURN_OFFSET]
+}
+
+#[rustfmt::skip]
+pub const POLYGLOT_RANDOM: [u64; NUM_POLYGLOT_KEYS] = [
+    0x9D39247E33776D41u64, 0x2AF7398005AAA5C7u64, 0x44DB015024623547u64, 0x9C15F73E62A76AE2u64,
+    0x75834465489C0C89u64, 0x3290AC3A203001BFu64, 0x0FBBAD1F61042279u64, 0xE83A908FF2FB60CAu64,
+    0x0D7E765D58755C10u64, 0x1A083822CEAFE02Du64, 0x9605D5F0E25EC3B0u64, 0xD021FF5CD13A2ED5u64,
+    0x40BDF15D4A672E32u64, 0x011355146FD56395u64, 0x5DB4832046F3D9E5u64, 0x239F8B2D7FF719CCu64,
+    0x05D1A1AE85B49AA1u64, 0x679F848F6E8FC971u64, 0x7449BBFF801FED0Bu64, 0x7D11CDB1C3B7ADF0u64,
+    0x82C7709E781EB7CCu64, 0xF3218F1C9510786Cu64, 0x331478F3AF51BBE6u64, 0x4BB38DE5E7219443u64,
+    0xAA649C6EBCFD50FCu64, 0x8DBD98A352AFD40Bu64, 0x87D2074B81D79217u64, 0x19F3C751D3E92AE1u64,
+    0xB4AB30F062B19ABFu64, 0x7B0500AC42047AC4u64, 0xC9452CA81A09D85Du64, 0x24AA6C514DA27500u64,
+    0x4C9F34427501B447u64, 0x14A68FD73C910841u64, 0xA71B9B83461CBD93u64, 0x03488B95B0F1850Fu64,
+    0x637B2B34FF93C040u64, 0x09D1BC9A3DD90A94u64, 0x3575668334A1DD3Bu64, 0x735E2B97A4C45A23u64,
+    0x18727070F1BD400Bu64, 0x1FCBACD259BF02E7u64, 0xD310A7C2CE9B6555u64, 0xBF983FE0FE5D8244u64,
+    0x9F74D14F7454A824u64, 0x51EBDC4AB9BA3035u64, 0x5C82C505DB9AB0FAu64, 0xFCF7FE8A3430B241u64,
+    0x3253A729B9BA3DDEu64, 0x8C74C368081B3075u64, 0xB9BC6C87167C33E7u64, 0x7EF48F2B83024E20u64,
+    0x11D505D4C351BD7Fu64, 0x6568FCA92C76A243u64, 0x4DE0B0F40F32A7B8u64, 0x96D693460CC37E5Du64,
+    0x42E240CB63689F2Fu64, 0x6D2BDCDAE2919661u64, 0x42880B0236E4D951u64, 0x5F0F4A5898171BB6u64,
+    0x39F890F579F92F88u64, 0x93C5B5F47356388Bu64, 0x63DC359D8D231B78u64, 0xEC16CA8AEA98AD76u64,
+    0x5355F900C2A82DC7u64, 0x07FB9F855A997142u64, 0x5093417AA8A7ED5Eu64, 0x7BCBC38DA25A7F3Cu64,
+    0x19FC8A768CF4B6D4u64, 0x637A7780DECFC0D9u64, 0x8249A47AEE0E41F7u64, 0x79AD695501E7D1E8u64,
+    0x14ACBAF4777D5776u64, 0xF145B6BECCDEA195u64, 0xDABF2AC8201752FCu64, 0x24C3C94DF9C8D3F6u64,
+    0xBB6E2924F03912EAu64, 0x0CE26C0B95C980D9u64, 0xA49CD132BFBF7CC4u64, 0xE99D662AF4243939u64,
+    0x27E6AD7891165C3Fu64, 0x8535F040B9744FF1u64, 0x54B3F4FA5F40D873u64, 0x72B12C32127FED2Bu64,
+    0xEE954D3C7B411F47u64, 0x9A85AC909A24EAA1u64, 0x70AC4CD9F04F21F5u64, 0xF9B89D3E99A075C2u64,
+    0x87B3E2B2B5C907B1u64, 0xA366E5B8C54F48B8u64, 0xAE4A9346CC3F7CF2u64, 0x1920C04D47267BBDu64,
+    0x87BF02C6B49E2AE9u64, 0x092237AC237F3859u64, 0xFF07F64EF8ED14D0u64, 0x8DE8DCA9F03CC54Eu64,
+    0x9C1633264DB49C89u64, 0xB3F22C3D0B0B38EDu64, 0x390E5FB44D01144Bu64, 0x5BFEA5B4712768E9u64,
+    0x1E1032911FA78984u64, 0x9A74ACB964E78CB3u64, 0x4F80F7A035DAFB04u64, 0x6304D09A0B3738C4u64,
+    0x2171E64683023A08u64, 0x5B9B63EB9CEFF80Cu64, 0x506AACF489889342u64, 0x1881AFC9A3A701D6u64,
+    0x6503080440750644u64, 0xDFD395339CDBF4A7u64, 0xEF927DBCF00C20F2u64, 0x7B32F7D1E03680ECu64,
+    0xB9FD7620E7316243u64, 0x05A7E8A57DB91B77u64, 0xB5889C6E15630A75u64, 0x4A750A09CE9573F7u64,
+    0xCF464CEC899A2F8Au64, 0xF538639CE705B824u64, 0x3C79A0FF5580EF7Fu64, 0xEDE6C87F8477609Du64,
+    0x799E81F05BC93F31u64, 0x86536B8CF3428A8Cu64, 0x97D7374C60087B73u64, 0xA246637CFF328532u64,
+    0x043FCAE60CC0EBA0u64, 0x920E449535DD359Eu64, 0x70EB093B15B290CCu64, 0x73A1921916591CBDu64,
+    0x56436C9FE1A1AA8Du64, 0xEFAC4B70633B8F81u64, 0xBB215798D45DF7AFu64, 0x45F20042F24F1768u64,
+    0x930F80F4E8EB7462u64, 0xFF6712FFCFD75EA1u64, 0xAE623FD67468AA70u64, 0xDD2C5BC84BC8D8FCu64,
+    0x7EED120D54CF2DD9u64, 0x22FE545401165F1Cu64, 0xC91800E98FB99929u64, 0x808BD68E6AC10365u64,
+    0xDEC468145B7605F6u64, 0x1BEDE3A3AEF53302u64, 0x43539603D6C55602u64, 0xAA969B5C691CCB7Au64,
+    0xA87832D392EFEE56u64, 0x65942C7B3C7E11AEu64, 0xDED2D633CAD004F6u64, 0x21F08570F420E565u64,
+    0xB415938D7DA94E3Cu64, 0x91B859E59ECB6350u64, 0x10CFF333E0ED804Au64, 0x28AED140BE0BB7DDu64,
+    0xC5CC1D89724FA456u64, 0x5648F680F11A2741u64, 0x2D255069F0B7DAB3u64, 0x9BC5A38EF729ABD4u64,
+    0xEF2F054308F6A2BCu64, 0xAF2042F5CC5C2858u64, 0x480412BAB7F5BE2Au64, 0xAEF3AF4A563DFE43u64,
+    0x19AFE59AE451497Fu64, 0x52593803DFF1E840u64, 0xF4F076E65F2CE6F0u64, 0x11379625747D5AF3u64,
+    0xBCE5D2248682C115u64, 0x9DA4243DE836994Fu64, 0x066F70B33FE09017u64, 0x4DC4DE189B671A1Cu64,
+    0x51039AB7712457C3u64, 0xC07A3F80C31FB4B4u64, 0xB46EE9C5E64A6E7Cu64, 0xB3819A42ABE61C87u64,
+    0x21A007933A522A20u64, 0x2DF16F761598AA4Fu64, 0x763C4A1371B368FDu64, 0xF793C46702E086A0u64,
+    0xD7288E012AEB8D31u64, 0xDE336A2A4BC1C44Bu64, 0x0BF692B38D079F23u64, 0x2C604A7A177326B3u64,
+    0x4850E73E03EB6064u64, 0xCFC447F1E53C8E1Bu64, 0xB05CA3F564268D99u64, 0x9AE182C8BC9474E8u64,
+    0xA4FC4BD4FC5558CAu64, 0xE755178D58FC4E76u64, 0x69B97DB1A4C03DFEu64, 0xF9B5B7C4ACC67C96u64,
+    0xFC6A82D64B8655FBu64, 0x9C684CB6C4D24417u64, 0x8EC97D2917456ED0u64, 0x6703DF9D2924E97Eu64,
+    0xC547F57E42A7444Eu64, 0x78E37644E7CAD29Eu64, 0xFE9A44E9362F05FAu64, 0x08BD35CC38336615u64,
+    0x9315E5EB3A129ACEu64, 0x94061B871E04DF75u64, 0xDF1D9F9D784BA010u64, 0x3BBA57B68871B59Du64,
+    0xD2B7ADEEDED1F73Fu64, 0xF7A255D83BC373F8u64, 0xD7F4F2448C0CEB81u64, 0xD95BE88CD210FFA7u64,
+    0x336F52F8FF4728E7u64, 0xA74049DAC312AC71u64, 0xA2F61BB6E437FDB5u64, 0x4F2A5CB07F6A35B3u64,
+    0x87D380BDA5BF7859u64, 0x16B9F7E06C453A21u64, 0x7BA2484C8A0FD54Eu64, 0xF3A678CAD9A2E38Cu64,
+    0x39B0BF7DDE437BA2u64, 0xFCAF55C1BF8A4424u64, 0x18FCF680573FA594u64, 0x4C0563B89F495AC3u64,
+    0x40E087931A00930Du64, 0x8CFFA9412EB642C1u64, 0x68CA39053261169Fu64, 0x7A1EE967D27579E2u64,
+    0x9D1D60E5076F5B6Fu64, 0x3810E399B6F65BA2u64, 0x32095B6D4AB5F9B1u64, 0x35CAB62109DD038Au64,
+    0xA90B24499FCFAFB1u64, 0x77A225A07CC2C6BDu64, 0x513E5E634C70E331u64, 0x4361C0CA3F692F12u64,
+    0xD941ACA44B20A45Bu64, 0x528F7C8602C5807Bu64, 0x52AB92BEB9613989u64, 0x9D1DFA2EFC557F73u64,
+    0x722FF175F572C348u64, 0x1D1260A51107FE97u64, 0x7A249A57EC0C9BA2u64, 0x04208FE9E8F7F2D6u64,
+    0x5A110C6058B920A0u64, 0x0CD9A497658A5698u64, 0x56FD23C8F9715A4Cu64, 0x284C847B9D887AAEu64,
+    0x04FEABFBBDB619CBu64, 0x742E1E651C60BA83u64, 0x9A9632E65904AD3Cu64, 0x881B82A13B51B9E2u64,
+    0x506E6744CD974924u64, 0xB0183DB56FFC6A79u64, 0x0ED9B915C66ED37Eu64, 0x5E11E86D5873D484u64,
+    0xF678647E3519AC6Eu64, 0x1B85D488D0F20CC5u64, 0xDAB9FE6525D89021u64, 0x0D151D86ADB73615u64,
+    0xA865A54EDCC0F019u64, 0x93C42566AEF98FFBu64, 0x99E7AFEABE000731u64, 0x48CBFF086DDF285Au64,
+    0x7F9B6AF1EBF78BAFu64, 0x58627E1A149BBA21u64, 0x2CD16E2ABD791E33u64, 0xD363EFF5F0977996u64,
+    0x0CE2A38C344A6EEDu64, 0x1A804AADB9CFA741u64, 0x907F30421D78C5DEu64, 0x501F65EDB3034D07u64,
+    0x37624AE5A48FA6E9u64, 0x957BAF61700CFF4Eu64, 0x3A6C27934E31188Au64, 0xD49503536ABCA345u64,
+    0x088E049589C432E0u64, 0xF943AEE7FEBF21B8u64, 0x6C3B8E3E336139D3u64, 0x364F6FFA464EE52Eu64,
+    0xD60F6DCEDC314222u64, 0x56963B0DCA418FC0u64, 0x16F50EDF91E513AFu64, 0xEF1955914B609F93u64,
+    0x565601C0364E3228u64, 0xECB53939887E8175u64, 0xBAC7A9A18531294Bu64, 0xB344C470397BBA52u64,
+    0x65D34954DAF3CEBDu64, 0xB4B81B3FA97511E2u64, 0xB422061193D6F6A7u64, 0x071582401C38434Du64,
+    0x7A13F18BBEDC4FF5u64, 0xBC4097B116C524D2u64, 0x59B97885E2F2EA28u64, 0x99170A5DC3115544u64,
+    0x6F423357E7C6A9F9u64, 0x325928EE6E6F8794u64, 0xD0E4366228B03343u64, 0x565C31F7DE89EA27u64,
+    0x30F5611484119414u64, 0xD873DB391292ED4Fu64, 0x7BD94E1D8E17DEBCu64, 0xC7D9F16864A76E94u64,
+    0x947AE053EE56E63Cu64, 0xC8C93882F9475F5Fu64, 0x3A9BF55BA91F81CAu64, 0xD9A11FBB3D9808E4u64,
+    0x0FD22063EDC29FCAu64, 0xB3F256D8ACA0B0B9u64, 0xB03031A8B4516E84u64, 0x35DD37D5871448AFu64,
+    0xE9F6082B05542E4Eu64, 0xEBFAFA33D7254B59u64, 0x9255ABB50D532280u64, 0xB9AB4CE57F2D34F3u64,
+    0x693501D628297551u64, 0xC62C58F97DD949BFu64, 0xCD454F8F19C5126Au64, 0xBBE83F4ECC2BDECBu64,
+    0xDC842B7E2819E230u64, 0xBA89142E007503B8u64, 0xA3BC941D0A5061CBu64, 0xE9F6760E32CD8021u64,
+    0x09C7E552BC76492Fu64, 0x852F54934DA55CC9u64, 0x8107FCCF064FCF56u64, 0x098954D51FFF6580u64,
+    0x23B70EDB1955C4BFu64, 0xC330DE426430F69Du64, 0x4715ED43E8A45C0Au64, 0xA8D7E4DAB780A08Du64,
+    0x0572B974F03CE0BBu64, 0xB57D2E985E1419C7u64, 0xE8D9ECBE2CF3D73Fu64, 0x2FE4B17170E59750u64,
+    0x11317BA87905E790u64, 0x7FBF21EC8A1F45ECu64, 0x1725CABFCB045B00u64, 0x964E915CD5E2B207u64,
+    0x3E2B8BCBF016D66Du64, 0xBE7444E39328A0ACu64, 0xF85B2B4FBCDE44B7u64, 0x49353FEA39BA63B1u64,
+    0x1DD01AAFCD53486Au64, 0x1FCA8A92FD719F85u64, 0xFC7C95D827357AFAu64, 0x18A6A990C8B35EBDu64,
+    0xCCCB7005C6B9C28Du64, 0x3BDBB92C43B17F26u64, 0xAA70B5B4F89695A2u64, 0xE94C39A54A98307Fu64,
+    0xB7A0B174CFF6F36Eu64, 0xD4DBA84729AF48ADu64, 0x2E18BC1AD9704A68u64, 0x2DE0966DAF2F8B1Cu64,
+    0xB9C11D5B1E43A07Eu64, 0x64972D68DEE33360u64, 0x94628D38D0C20584u64, 0xDBC0D2B6AB90A559u64,
+    0xD2733C4335C6A72Fu64, 0x7E75D99D94A70F4Du64, 0x6CED1983376FA72Bu64, 0x97FCAACBF030BC24u64,
+    0x7B77497B32503B12u64, 0x8547EDDFB81CCB94u64, 0x79999CDFF70902CBu64, 0xCFFE1939438E9B24u64,
+    0x829626E3892D95D7u64, 0x92FAE24291F2B3F1u64, 0x63E22C147B9C3403u64, 0xC678B6D860284A1Cu64,
+    0x5873888850659AE7u64, 0x0981DCD296A8736Du64, 0x9F65789A6509A440u64, 0x9FF38FED72E9052Fu64,
+    0xE479EE5B9930578Cu64, 0xE7F28ECD2D49EECDu64, 0x56C074A581EA17FEu64, 0x5544F7D774B14AEFu64,
+    0x7B3F0195FC6F290Fu64, 0x12153635B2C0CF57u64, 0x7F5126DBBA5E0CA7u64, 0x7A76956C3EAFB413u64,
+    0x3D5774A11D31AB39u64, 0x8A1B083821F40CB4u64, 0x7B4A38E32537DF62u64, 0x950113646D1D6E03u64,
+    0x4DA8979A0041E8A9u64, 0x3BC36E078F7515D7u64, 0x5D0A12F27AD310D1u64, 0x7F9D1A2E1EBE1327u64,
+    0xDA3A361B1C5157B1u64, 0xDCDD7D20903D0C25u64, 0x36833336D068F707u64, 0xCE68341F79893389u64,
+    0xAB9090168DD05F34u64, 0x43954B3252DC25E5u64, 0xB438C2B67F98E5E9u64, 0x10DCD78E3851A492u64,
+    0xDBC27AB5447822BFu64, 0x9B3CDB65F82CA382u64, 0xB67B7896167B4C84u64, 0xBFCED1B0048EAC50u64,
+    0xA9119B60369FFEBDu64, 0x1FFF7AC80904BF45u64, 0xAC12FB171817EEE7u64, 0xAF08DA9177DDA93Du64,
+    0x1B0CAB936E65C744u64, 0xB559EB1D04E5E932u64, 0xC37B45B3F8D6F2BAu64, 0xC3A9DC228CAAC9E9u64,
+    0xF3B8B6675A6507FFu64, 0x9FC477DE4ED681DAu64, 0x67378D8ECCEF96CBu64, 0x6DD856D94D259236u64,
+    0xA319CE15B0B4DB31u64, 0x073973751F12DD5Eu64, 0x8A8E849EB32781A5u64, 0xE1925C71285279F5u64,
+    0x74C04BF1790C0EFEu64, 0x4DDA48153C94938Au64, 0x9D266D6A1CC0542Cu64, 0x7440FB816508C4FEu64,
+    0x13328503DF48229Fu64, 0xD6BF7BAEE43CAC40u64, 0x4838D65F6EF6748Fu64, 0x1E152328F3318DEAu64,
+    0x8F8419A348F296BFu64, 0x72C8834A5957B511u64, 0xD7A023A73260B45Cu64, 0x94EBC8ABCFB56DAEu64,
+    0x9FC10D0F989993E0u64, 0xDE68A2355B93CAE6u64, 0xA44CFE79AE538BBEu64, 0x9D1D84FCCE371425u64,
+    0x51D2B1AB2DDFB636u64, 0x2FD7E4B9E72CD38Cu64, 0x65CA5B96B7552210u64, 0xDD69A0D8AB3B546Du64,
+    0x604D51B25FBF70E2u64, 0x73AA8A564FB7AC9Eu64, 0x1A8C1E992B941148u64, 0xAAC40A2703D9BEA0u64,
+    0x764DBEAE7FA4F3A6u64, 0x1E99B96E70A9BE8Bu64, 0x2C5E9DEB57EF4743u64, 0x3A938FEE32D29981u64,
+    0x26E6DB8FFDF5ADFEu64, 0x469356C504EC9F9Du64, 0xC8763C5B08D1908Cu64, 0x3F6C6AF859D80055u64,
+    0x7F7CC39420A3A545u64, 0x9BFB227EBDF4C5CEu64, 0x89039D79D6FC5C5Cu64, 0x8FE88B57305E2AB6u64,
+    0xA09E8C8C35AB96DEu64, 0xFA7E393983325753u64, 0xD6B6D0ECC617C699u64, 0xDFEA21EA9E7557E3u64,
+    0xB67C1FA481680AF8u64, 0xCA1E3785A9E724E5u64, 0x1CFC8BED0D681639u64, 0xD18D8549D140CAEAu64,
+    0x4ED0FE7E9DC91335u64, 0xE4DBF0634473F5D2u64, 0x1761F93A44D5AEFEu64, 0x53898E4C3910DA55u64,
+    0x734DE8181F6EC39Au64, 0x2680B122BAA28D97u64, 0x298AF231C85BAFABu64, 0x7983EED3740847D5u64,
+    0x66C1A2A1A60CD889u64, 0x9E17E49642A3E4C1u64, 0xEDB454E7BADC0805u64, 0x50B704CAB602C329u64,
+    0x4CC317FB9CDDD023u64, 0x66B4835D9EAFEA22u64, 0x219B97E26FFC81BDu64, 0x261E4E4C0A333A9Du64,
+    0x1FE2CCA76517DB90u64, 0xD7504DFA8816EDBBu64, 0xB9571FA04DC089C8u64, 0x1DDC0325259B27DEu64,
+    0xCF3F4688801EB9AAu64, 0xF4F5D05C10CAB243u64, 0x38B6525C21A42B0Eu64, 0x36F60E2BA4FA6800u64,
+    0xEB3593803173E0CEu64, 0x9C4CD6257C5A3603u64, 0xAF0C317D32ADAA8Au64, 0x258E5A80C7204C4Bu64,
+    0x8B889D624D44885Du64, 0xF4D14597E660F855u64, 0xD4347F66EC8941C3u64, 0xE699ED85B0DFB40Du64,
+    0x2472F6207C2D0484u64, 0xC2A1E7B5B459AEB5u64, 0xAB4F6451CC1D45ECu64, 0x63767572AE3D6174u64,
+    0xA59E0BD101731A28u64, 0x116D0016CB948F09u64, 0x2CF9C8CA052F6E9Fu64, 0x0B090A7560A968E3u64,
+    0xABEEDDB2DDE06FF1u64, 0x58EFC10B06A2068Du64, 0xC6E57A78FBD986E0u64, 0x2EAB8CA63CE802D7u64,
+    0x14A195640116F336u64, 0x7C0828DD624EC390u64, 0xD74BBE77E6116AC7u64, 0x804456AF10F5FB53u64,
+    0xEBE9EA2ADF4321C7u64, 0x03219A39EE587A30u64, 0x49787FEF17AF9924u64, 0xA1E9300CD8520548u64,
+    0x5B45E522E4B1B4EFu64, 0xB49C3B3995091A36u64, 0xD4490AD526F14431u64, 0x12A8F216AF9418C2u64,
+    0x001F837CC7350524u64, 0x1877B51E57A764D5u64, 0xA2853B80F17F58EEu64, 0x993E1DE72D36D310u64,
+    0xB3598080CE64A656u64, 0x252F59CF0D9F04BBu64, 0xD23C8E176D113600u64, 0x1BDA0492E7E4586Eu64,
+    0x21E0BD5026C619BFu64, 0x3B097ADAF088F94Eu64, 0x8D14DEDB30BE846Eu64, 0xF95CFFA23AF5F6F4u64,
+    0x3871700761B3F743u64, 0xCA672B91E9E4FA16u64, 0x64C8E531BFF53B55u64, 0x241260ED4AD1E87Du64,
+    0x106C09B972D2E822u64, 0x7FBA195410E5CA30u64, 0x7884D9BC6CB569D8u64, 0x0647DFEDCD894A29u64,
+    0x63573FF03E224774u64, 0x4FC8E9560F91B123u64, 0x1DB956E450275779u64, 0xB8D91274B9E9D4FBu64,
+    0xA2EBEE47E2FBFCE1u64, 0xD9F1F30CCD97FB09u64, 0xEFED53D75FD64E6Bu64, 0x2E6D02C36017F67Fu64,
+    0xA9AA4D20DB084E9Bu64, 0xB64BE8D8B25396C1u64, 0x70CB6AF7C2D5BCF0u64, 0x98F076A4F7A2322Eu64,
+    0xBF84470805E69B5Fu64, 0x94C3251F06F90CF3u64, 0x3E003E616A6591E9u64, 0xB925A6CD0421AFF3u64,
+    0x61BDD1307C66E300u64, 0xBF8D5108E27E0D48u64, 0x240AB57A8B888B20u64, 0xFC87614BAF287E07u64,
+    0xEF02CDD06FFDB432u64, 0xA1082C0466DF6C0Au64, 0x8215E577001332C8u64, 0xD39BB9C3A48DB6CFu64,
+    0x2738259634305C14u64, 0x61CF4F94C97DF93Du64, 0x1B6BACA2AE4E125Bu64, 0x758F450C88572E0Bu64,
+    0x959F587D507A8359u64, 0xB063E962E045F54Du64, 0x60E8ED72C0DFF5D1u64, 0x7B64978555326F9Fu64,
+    0xFD080D236DA814BAu64, 0x8C90FD9B083F4558u64, 0x106F72FE81E2C590u64, 0x7976033A39F7D952u64,
+    0xA4EC0132764CA04Bu64, 0x733EA705FAE4FA77u64, 0xB4D8F77BC3E56167u64, 0x9E21F4F903B33FD9u64,
+    0x9D765E419FB69F6Du64, 0xD30C088BA61EA5EFu64, 0x5D94337FBFAF7F5Bu64, 0x1A4E4822EB4D7A59u64,
+    0x6FFE73E81B637FB3u64, 0xDDF957BC36D8B9CAu64, 0x64D0E29EEA8838B3u64, 0x08DD9BDFD96B9F63u64,
+    0x087E79E5A57D1D13u64, 0xE328E230E3E2B3FBu64, 0x1C2559E30F0946BEu64, 0x720BF5F26F4D2EAAu64,
+    0xB0774D261CC609DBu64, 0x443F64EC5A371195u64, 0x4112CF68649A260Eu64, 0xD813F2FAB7F5C5CAu64,
+    0x660D3257380841EEu64, 0x59AC2C7873F910A3u64, 0xE846963877671A17u64, 0x93B633ABFA3469F8u64,
+    0xC0C0F5A60EF4CDCFu64, 0xCAF21ECD4377B28Cu64, 0x57277707199B8175u64, 0x506C11B9D90E8B1Du64,
+    0xD83CC2687A19255Fu64, 0x4A29C6465A314CD1u64, 0xED2DF21216235097u64, 0xB5635C95FF7296E2u64,
+    0x22AF003AB672E811u64, 0x52E762596BF68235u64, 0x9AEBA33AC6ECC6B0u64, 0x944F6DE09134DFB6u64,
+    0x6C47BEC883A7DE39u64, 0x6AD047C430A12104u64, 0xA5B1CFDBA0AB4067u64, 0x7C45D833AFF07862u64,
+    0x5092EF950A16DA0Bu64, 0x9338E69C052B8E7Bu64, 0x455A4B4CFE30E3F5u64, 0x6B02E63195AD0CF8u64,
+    0x6B17B224BAD6BF27u64, 0xD1E0CCD25BB9C169u64, 0xDE0C89A556B9AE70u64, 0x50065E535A213CF6u64,
+    0x9C1169FA2777B874u64, 0x78EDEFD694AF1EEDu64, 0x6DC93D9526A50E68u64, 0xEE97F453F06791EDu64,
+    0x32AB0EDB696703D3u64, 0x3A6853C7E70757A7u64, 0x31865CED6120F37Du64, 0x67FEF95D92607890u64,
+    0x1F2B1D1F15F6DC9Cu64, 0xB69E38A8965C6B65u64, 0xAA9119FF184CCCF4u64, 0xF43C732873F24C13u64,
+    0xFB4A3D794A9A80D2u64, 0x3550C2321FD6109Cu64, 0x371F77E76BB8417Eu64, 0x6BFA9AAE5EC05779u64,
+    0xCD04F3FF001A4778u64, 0xE3273522064480CAu64, 0x9F91508BFFCFC14Au64, 0x049A7F41061A9E60u64,
+    0xFCB6BE43A9F2FE9Bu64, 0x08DE8A1C7797DA9Bu64, 0x8F9887E6078735A1u64, 0xB5B4071DBFC73A66u64,
+    0x230E343DFBA08D33u64, 0x43ED7F5A0FAE657Du64, 0x3A88A0FBBCB05C63u64, 0x21874B8B4D2DBC4Fu64,
+    0x1BDEA12E35F6A8C9u64, 0x53C065C6C8E63528u64, 0xE34A1D250E7A8D6Bu64, 0xD6B04D3B7651DD7Eu64,
+    0x5E90277E7CB39E2Du64, 0x2C046F22062DC67Du64, 0xB10BB459132D0A26u64, 0x3FA9DDFB67E2F199u64,
+    0x0E09B88E1914F7AFu64, 0x10E8B35AF3EEAB37u64, 0x9EEDECA8E272B933u64, 0xD4C718BC4AE8AE5Fu64,
+    0x81536D601170FC20u64, 0x91B534F885818A06u64, 0xEC8177F83F900978u64, 0x190E714FADA5156Eu64,
+    0xB592BF39B0364963u64, 0x89C350C893AE7DC1u64, 0xAC042E70F8B383F2u64, 0xB49B52E587A1EE60u64,
+    0xFB152FE3FF26DA89u64, 0x3E666E6F69AE2C15u64, 0x3B544EBE544C19F9u64, 0xE805A1E290CF2456u64,
+    0x24B33C9D7ED25117u64, 0xE74733427B72F0C1u64, 0x0A804D18B7097475u64, 0x57E3306D881EDB4Fu64,
+    0x4AE7D6A36EB5DBCBu64, 0x2D8D5432157064C8u64, 0xD1E649DE1E7F268Bu64, 0x8A328A1CEDFE552Cu64,
+    0x07A3AEC79624C7DAu64, 0x84547DDC3E203C94u64, 0x990A98FD5071D263u64, 0x1A4FF12616EEFC89u64,
+    0xF6F7FD1431714200u64, 0x30C05B1BA332F41Cu64, 0x8D2636B81555A786u64, 0x46C9FEB55D120902u64,
+    0xCCEC0A73B49C9921u64, 0x4E9D2827355FC492u64, 0x19EBB029435DCB0Fu64, 0x4659D2B743848A2Cu64,
+    0x963EF2C96B33BE31u64, 0x74F85198B05A2E7Du64, 0x5A0F544DD2B1FB18u64, 0x03727073C2E134B1u64,
+    0xC7F6AA2DE59AEA61u64, 0x352787BAA0D7C22Fu64, 0x9853EAB63B5E0B35u64, 0xABBDCDD7ED5C0860u64,
+    0xCF05DAF5AC8D77B0u64, 0x49CAD48CEBF4A71Eu64, 0x7A4C10EC2158C4A6u64, 0xD9E92AA246BF719Eu64,
+    0x13AE978D09FE5557u64, 0x730499AF921549FFu64, 0x4E4B705B92903BA4u64, 0xFF577222C14F0A3Au64,
+    0x55B6344CF97AAFAEu64, 0xB862225B055B6960u64, 0xCAC09AFBDDD2CDB4u64, 0xDAF8E9829FE96B5Fu64,
+    0xB5FDFC5D3132C498u64, 0x310CB380DB6F7503u64, 0xE87FBB46217A360Eu64, 0x2102AE466EBB1148u64,
+    0xF8549E1A3AA5E00Du64, 0x07A69AFDCC42261Au64, 0xC4C118BFE78FEAAEu64, 0xF9F4892ED96BD438u64,
+    0x1AF3DBE25D8F45DAu64, 0xF5B4B0B0D2DEEEB4u64, 0x962ACEEFA82E1C84u64, 0x046E3ECAAF453CE9u64,
+    0xF05D129681949A4Cu64, 0x964781CE734B3C84u64, 0x9C2ED44081CE5FBDu64, 0x522E23F3925E319Eu64,
+    0x177E00F9FC32F791u64, 0x2BC60A63A6F3B3F2u64, 0x222BBFAE61725606u64, 0x486289DDCC3D6780u64,
+    0x7DC7785B8EFDFC80u64, 0x8AF38731C02BA980u64, 0x1FAB64EA29A2DDF7u64, 0xE4D9429322CD065Au64,
+    0x9DA058C67844F20Cu64, 0x24C0E332B70019B0u64, 0x233003B5A6CFE6ADu64, 0xD586BD01C5C217F6u64,
+    0x5E5637885F29BC2Bu64, 0x7EBA726D8C94094Bu64, 0x0A56A5F0BFE39272u64, 0xD79476A84EE20D06u64,
+    0x9E4C1269BAA4BF37u64, 0x17EFEE45B0DEE640u64, 0x1D95B0A5FCF90BC6u64, 0x93CBE0B699C2585Du64,
+    0x65FA4F227A2B6D79u64, 0xD5F9E858292504D5u64, 0xC2B5A03F71471A6Fu64, 0x59300222B4561E00u64,
+    0xCE2F8642CA0712DCu64, 0x7CA9723FBB2E8988u64, 0x2785338347F2BA08u64, 0xC61BB3A141E50E8Cu64,
+    0x150F361DAB9DEC26u64, 0x9F6A419D382595F4u64, 0x64A53DC924FE7AC9u64, 0x142DE49FFF7A7C3Du64,
+    0x0C335248857FA9E7u64, 0x0A9C32D5EAE45305u64, 0xE6C42178C4BBB92Eu64, 0x71F1CE2490D20B07u64,
+    0xF1BCC3D275AFE51Au64, 0xE728E8C83C334074u64, 0x96FBF83A12884624u64, 0x81A1549FD6573DA5u64,
+    0x5FA7867CAF35E149u64, 0x56986E2EF3ED091Bu64, 0x917F1DD5F8886C61u64, 0xD20D8C88C8FFE65Fu64,
+    0x31D71DCE64B2C310u64, 0xF165B587DF898190u64, 0xA57E6339DD2CF3A0u64, 0x1EF6E6DBB1961EC9u64,
+    0x70CC73D90BC26E24u64, 0xE21A6B35DF0C3AD7u64, 0x003A93D8B2806962u64, 0x1C99DED33CB890A1u64,
+    0xCF3145DE0ADD4289u64, 0xD0E4427A5514FB72u64, 0x77C621CC9FB3A483u64, 0x67A34DAC4356550Bu64,
+    0xF8D626AAAF278509u64,
+];
+
+#[test]
+fn polyglot_reference_keys() {
+    use crate::board::Board;
+
+    let mut board = Board::default();
+    assert_eq!(board.hash(), 0x463B96181691FC9C);
+    let expected = [
+        ("e2e4", 0x823C9B50FD114196),
+        ("d7d5", 0x0756B94461C50FB0),
+        ("e4e5", 0x662FAFB965DB29D4),
+        ("f7f5", 0x22A48B5A8E47FF78),
+        ("e1e2", 0x652A607CA3F242C1),
+        ("e8f7", 0x00FDD303C946BDD9),
+    ];
+    for (notation, key) in expected {
+        let m = board.parse_uci_move(notation).expect("move should be legal");
+        board.make_move(m);
+        assert_eq!(board.hash(), key, "{notation}");
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::{bail, Context};
+use chess_core::{board::piece::Side, book::{BookBuilder, BookOptions}};
+
+use super::flag_value;
+
+const USAGE: &str = "usage: chess-engine book --output <book.bin> [--ply <n>] [--min-games <n>] [--side <white|black>] <games.pgn>...";
+
+/// Build a Polyglot book from the given PGN files.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let mut options = BookOptions::default();
+    let mut output = None;
+    let mut inputs = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().context("missing value for --output")?.clone()),
+            "--ply" => options.max_ply = flag_value(arg, args.next())?,
+            "--min-games" => options.min_games = flag_value(arg, args.next())?,
+            "--side" => options.side = match args.next().map(String::as_str) {
+                Some("white" | "w") => Some(Side::White),
+                Some("black" | "b") => Some(Side::Black),
+                Some("both") => None,
+                _ => bail!("--side must be white, black or both"),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            },
+            flag if flag.starts_with('-') => bail!("unknown option: {flag}\n{USAGE}"),
+            path => inputs.push(path.to_string()),
+        }
+    }
+    let output = output.with_context(|| format!("missing --output\n{USAGE}"))?;
+    if inputs.is_empty() {
+        bail!("no PGN files given\n{USAGE}");
+    }
+
+    let mut builder = BookBuilder::new(options);
+    for path in inputs.iter() {
+        let text = std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+        builder.add_pgn(&text);
+    }
+    let book = builder.build();
+    book.save(&output)?;
+    println!("{} games, {} skipped, {} entries written to {output}", builder.games(), builder.skipped(), book.len());
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use chess_core::epd::parse_epd;
+
+use crate::search::alphabeta::{search, SearchLimits};
+
+use super::flag_value;
+
+const USAGE: &str = "usage: chess-engine epd [--time <ms>] [--nodes <n>] [--depth <n>] <suite.epd>...
+
+Searches every position of the test suites and checks the result against its bm and am opcodes.
+Each position gets one second when no limit is given.";
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+
+/// Run EPD test suites and report the solved positions.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let mut limits = SearchLimits::default();
+    let mut inputs = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time" => limits.time = Some(Duration::from_millis(flag_value(arg, args.next())?)),
+            "--nodes" => limits.nodes = Some(flag_value(arg, args.next())?),
+            "--depth" => limits.depth = Some(flag_value(arg, args.next())?),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            },
+            flag if flag.starts_with('-') => bail!("unknown option: {flag}\n{USAGE}"),
+            path => inputs.push(path.to_string()),
+        }
+    }
+    if inputs.is_empty() {
+        bail!("no EPD files given\n{USAGE}");
+    }
+    if limits.time.is_none() && limits.nodes.is_none() && limits.depth.is_none() {
+        limits.time = Some(DEFAULT_MOVE_TIME);
+    }
+
+    let start = Instant::now();
+    let mut total = 0;
+    let mut solved = 0;
+    let mut nodes = 0;
+    for path in inputs.iter() {
+        let text = std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+        let suite = parse_epd(&text).with_context(|| format!("failed to parse {path}"))?;
+        for (n, epd) in suite.iter().enumerate() {
+            let id = epd.id().map(str::to_string).unwrap_or_else(|| format!("{path}:{}", n + 1));
+            let best_moves = epd.best_moves().with_context(|| format!("invalid position {id}"))?;
+            let avoid_moves = epd.avoid_moves().with_context(|| format!("invalid position {id}"))?;
+            if best_moves.is_empty() && avoid_moves.is_empty() {
+                println!("skip {id}: no bm or am opcode");
+                continue;
+            }
+            let result = search(&epd.board, limits.clone());
+            nodes += result.nodes;
+            total += 1;
+            let found = result.best;
+            if (best_moves.is_empty() || best_moves.contains(&found)) && !avoid_moves.contains(&found) {
+                solved += 1;
+                continue;
+            }
+            let expected = |moves: &[_]| moves.iter().map(|m| epd.board.san(*m)).collect::<Vec<_>>().join(" ");
+            let mut reason = String::new();
+            if !best_moves.is_empty() {
+                reason.push_str(&format!("bm {}", expected(&best_moves)));
+            }
+            if !avoid_moves.is_empty() {
+                reason.push_str(&format!("{}am {}", if reason.is_empty() { "" } else { ", " }, expected(&avoid_moves)));
+            }
+            println!("fail {id}: played {} (depth {}), expected {reason}", epd.board.san(found), result.depth);
+        }
+    }
+
+    let elapsed = start.elapsed();
+    let percent = if total == 0 { 0.0 } else { solved as f64 * 100.0 / total as f64 };
+    println!("solved {solved}/{total} ({percent:.1}%), {} failed", total - solved);
+    println!("{nodes} nodes in {:.2}s", elapsed.as_secs_f64());
+    Ok(())
+}
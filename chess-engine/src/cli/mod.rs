@@ -0,0 +1,47 @@
+//! Command line tools that run instead of the UCI loop when the engine is given arguments.
+
+use anyhow::{bail, Context};
+
+pub mod bench;
+mod book;
+mod epd;
+mod smp;
+mod tablebase;
+
+const USAGE: &str = "usage: chess-engine [bench|book|epd|smp|tablebase] [options]
+
+Runs the UCI protocol on stdin/stdout when no command is given.
+
+commands:
+    bench        Search a fixed set of positions and print the node count and speed
+    book         Build a Polyglot opening book from PGN files
+    epd          Run EPD test suites such as WAC or STS
+    smp          Measure the time to depth with 1 to n search threads
+    tablebase    Generate endgame tablebases by retrograde analysis";
+
+/// Run the command given on the command line.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("bench") => bench::run(&args[1..]),
+        Some("book") => book::run(&args[1..]),
+        Some("epd") => epd::run(&args[1..]),
+        Some("smp") => smp::run(&args[1..]),
+        Some("tablebase") => tablebase::run(&args[1..]),
+        Some("help" | "-h" | "--help") => {
+            println!("{USAGE}");
+            Ok(())
+        },
+        Some(other) => bail!("unknown command: {other}\n\n{USAGE}"),
+        None => bail!("{USAGE}"),
+    }
+}
+
+/// Parse the value following a flag.
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> anyhow::Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value.with_context(|| format!("missing value for {flag}"))?
+        .parse()
+        .with_context(|| format!("invalid value for {flag}"))
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use chess_core::board::Board;
+
+use crate::search::{alphabeta::{search_with, SearchLimits}, tt::TranspositionTable};
+
+use super::flag_value;
+
+const USAGE: &str = "usage: chess-engine smp [--depth <n>] [--threads <n>] [--hash <mb>]
+
+Searches a set of positions to a fixed depth with 1 to n threads and reports the time to depth
+and the speedup over a single thread. Each search starts with an empty hash table.";
+const DEFAULT_DEPTH: usize = 7;
+const DEFAULT_HASH_CAPACITY: usize = 64;
+const POSITIONS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N2N2/PP2BPPP/R1BQ1RK1 w - - 0 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "6k1/5ppp/8/8/3q4/8/3R1PPP/3R2K1 b - - 0 1",
+];
+
+/// Measure how the time to reach a depth scales with the number of search threads.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let mut depth = DEFAULT_DEPTH;
+    let mut threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut hash_capacity = DEFAULT_HASH_CAPACITY;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = flag_value(arg, args.next())?,
+            "--threads" => threads = flag_value(arg, args.next())?,
+            "--hash" => hash_capacity = flag_value(arg, args.next())?,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            },
+            other => bail!("unknown option: {other}\n{USAGE}"),
+        }
+    }
+    if threads == 0 {
+        bail!("--threads must be at least 1");
+    }
+
+    let boards = POSITIONS.iter().map(|fen| {
+        let mut board = Board::new();
+        board.load_fen(fen).with_context(|| format!("invalid position {fen}"))?;
+        Ok(board)
+    }).collect::<anyhow::Result<Vec<_>>>()?;
+    let mut single = None;
+    println!("{:>7} {:>10} {:>12} {:>10} {:>8}", "threads", "time (ms)", "nodes", "nps", "speedup");
+    for n in 1..=threads {
+        let mut time = Duration::ZERO;
+        let mut nodes = 0;
+        for board in boards.iter() {
+            let tt = TranspositionTable::new(hash_capacity)?;
+            let start = Instant::now();
+            let result = search_with(board, SearchLimits { depth: Some(depth), ..Default::default() }, &tt, n, |_| {});
+            time += start.elapsed();
+            nodes += result.nodes;
+        }
+        let single = *single.get_or_insert(time);
+        let nps = (nodes as u128 * 1000 / time.as_millis().max(1)) as u64;
+        println!("{n:>7} {:>10} {nodes:>12} {nps:>10} {:>7.2}x", time.as_millis(), single.as_secs_f64() / time.as_secs_f64());
+    }
+    Ok(())
+}
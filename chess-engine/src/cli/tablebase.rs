@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+use anyhow::{bail, Context};
+use chess_core::tablebase::Tablebase;
+
+const USAGE: &str = "usage: chess-engine tablebase --dir <directory> <signature>...
+
+Signatures list the white pieces then the black pieces, ie. KQK, KRK, KPK, KBNK or KRKP.";
+
+/// Generate endgame tables and the smaller tables they depend on, then save them to a directory.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let mut dir = None;
+    let mut signatures = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--dir" => dir = Some(args.next().context("missing value for --dir")?.clone()),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            },
+            flag if flag.starts_with('-') => bail!("unknown option: {flag}\n{USAGE}"),
+            signature => signatures.push(signature.to_ascii_uppercase()),
+        }
+    }
+    let dir = dir.with_context(|| format!("missing --dir\n{USAGE}"))?;
+    if signatures.is_empty() {
+        bail!("no signatures given\n{USAGE}");
+    }
+
+    let mut tablebase = Tablebase::load_dir(&dir).unwrap_or_default();
+    for signature in signatures.iter() {
+        let start = Instant::now();
+        tablebase.generate(signature)?;
+        println!("{signature} generated in {:.1}s", start.elapsed().as_secs_f64());
+    }
+    tablebase.save_dir(&dir)?;
+    println!("{} tables written to {dir}: {}", tablebase.signatures().len(), tablebase.signatures().join(" "));
+    Ok(())
+}
@@ -1,13 +1,18 @@
-use chess_core::board::Board;
-use crate::uci::{IdEvent, OptionEvent, UciEvent};
-use crate::search::{SearchTree, Searching};
+use std::{sync::{atomic::AtomicBool, Arc, Mutex}, thread, time::Duration};
 
-/// Default size of hash table in MB.
-const DEFAULT_HASH_CAPACITY: usize = 512;
-const DEFAULT_ENABLE_PONDER: bool = false;
-const DEFAULT_NUM_THREADS: usize = 1;
-const ENGINE_NAME: &'static str = "MonteCristo";
-const ENGINE_AUTHOR: &'static str = "mosesprime";
+use chess_core::{board::{bitboard_square_iter, piece::Side, Board}, game::GameState, moves::{generate_legal_moves, perft, ShortMove}};
+use chess_uci::{GoCommand, GoKind, GoParam, IdEvent, InfoEvent, PositionCommand, ScoreEvent, UciError, UciEvent, UciOption, WdlEvent};
+use crate::{eval::{evaluate, evaluation_terms, kpk_score, Evaluation}, search::{alphabeta::{self, SearchLimits, SearchResult}, mate::{self, MateSearch}, mcts, time::TimeControl, tt::TranspositionTable, Backend, SearchTree, Searching}};
+
+use options::{OptionRegistry, DEFAULT_DEBUG_COMMANDS, DEFAULT_ENABLE_PONDER, DEFAULT_HASH_CAPACITY, DEFAULT_MOVE_OVERHEAD, DEFAULT_MULTI_PV, DEFAULT_NUM_THREADS, DEFAULT_SHOW_WDL};
+
+pub mod options;
+
+/// Centipawns at which a win is as likely as not, see [wdl].
+const WDL_OFFSET: i32 = 200;
+const WDL_SCALE: f64 = 60.0;
+const ENGINE_NAME: &str = "MonteCristo";
+const ENGINE_AUTHOR: &str = "mosesprime";
 
 pub struct EngineConfig {
     pub num_threads: usize,
@@ -17,6 +22,13 @@ pub struct EngineConfig {
     pub enable_debug: bool,
     pub enable_ponder: bool,
     pub enable_analyse: bool,
+    /// Send win, draw and loss chances with the score, see [wdl].
+    pub show_wdl: bool,
+    pub backend: Backend,
+    /// Milliseconds kept back per move, see [TimeControl::limits].
+    pub move_overhead: usize,
+    /// Accept the commands outside the protocol, see [UciCommand::is_extension](chess_uci::UciCommand::is_extension).
+    pub debug_commands: bool,
 }
 
 impl Default for EngineConfig {
@@ -27,25 +39,37 @@ impl Default for EngineConfig {
             num_threads: DEFAULT_NUM_THREADS,
             max_threads: available_threads,
             hash_capacity: DEFAULT_HASH_CAPACITY,
-            multi_pv: 1,
+            multi_pv: DEFAULT_MULTI_PV,
             enable_debug: false,
             enable_ponder: DEFAULT_ENABLE_PONDER,
             enable_analyse: false,
+            show_wdl: DEFAULT_SHOW_WDL,
+            backend: Backend::default(),
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            debug_commands: DEFAULT_DEBUG_COMMANDS,
         }
     }
 }
 
 pub struct Engine {
-    search_tree: SearchTree,
+    game: GameState,
+    /// Shared with the search thread, which holds the lock while it searches.
+    search_tree: Arc<Mutex<SearchTree>>,
+    tt: Arc<TranspositionTable>,
     searching: Option<Searching>,
+    options: OptionRegistry,
     pub(crate) config: EngineConfig,
 }
 
 impl Engine {
     pub fn new(config: EngineConfig) -> Self {
+        let (table_capacity, tree_capacity) = hash_budget(config.backend, config.hash_capacity);
         Self {
-            search_tree: SearchTree::new(Board::default()),
+            game: GameState::new(Board::default()),
+            search_tree: Arc::new(Mutex::new(SearchTree::new(Board::default(), tree_capacity))),
+            tt: Arc::new(TranspositionTable::new(table_capacity).expect("failed to allocate the hash table")),
             searching: None,
+            options: OptionRegistry::new(&config),
             config,
         }
     }
@@ -53,21 +77,401 @@ impl Engine {
     pub(crate) fn report_about(&self) {
         println!("{}", UciEvent::Id(IdEvent::Name(ENGINE_NAME.to_string())));
         println!("{}", UciEvent::Id(IdEvent::Author(ENGINE_AUTHOR.to_string())));
-        println!("{}", UciEvent::Option(OptionEvent::Threads(crate::uci::Spin { default: DEFAULT_NUM_THREADS, min: 1, max: self.config.max_threads })));
-        println!("{}", UciEvent::Option(OptionEvent::Hash(crate::uci::Spin { default: DEFAULT_HASH_CAPACITY, min: 1, max: usize::MAX })));
-        println!("{}", UciEvent::Option(OptionEvent::Ponder(crate::uci::Check { default: DEFAULT_ENABLE_PONDER })))
+        for option in self.options.options() {
+            println!("{}", UciEvent::Option(option.event()));
+        }
+    }
+
+    /// Validate a `setoption` command against the registry and apply it.
+    pub fn set_option(&mut self, option: &UciOption) -> anyhow::Result<()> {
+        let (apply, value) = self.options.parse(option)?;
+        apply(self, value)
+    }
+
+    fn search_tree(&self) -> std::sync::MutexGuard<'_, SearchTree> {
+        self.search_tree.lock().expect("search tree lock poisoned")
     }
 
     pub fn reset(&mut self, board: Board) {
-        if let Some(search) = &self.searching {
-            let _ = search.stop();
-            self.searching = None;
+        self.stop();
+        self.game = GameState::new(board.clone());
+        // Continuations keep the tree through `set_position`, anything else starts over.
+        self.search_tree().reset(board);
+    }
+
+    /// Resize the hash memory of the backend in use, see [hash_budget].
+    pub fn set_hash_capacity(&mut self, hash_capacity: usize) -> anyhow::Result<()> {
+        self.allocate_hash(self.config.backend, hash_capacity)
+    }
+
+    /// Switch the backend of `go`, moving the hash memory over to it.
+    pub fn set_backend(&mut self, backend: Backend) -> anyhow::Result<()> {
+        if backend == self.config.backend {
+            return Ok(());
+        }
+        self.allocate_hash(backend, self.config.hash_capacity)
+    }
+
+    /// Reallocate the hash table, which loses its entries, and budget the tree. Nothing changes if
+    /// the table can't be allocated.
+    fn allocate_hash(&mut self, backend: Backend, hash_capacity: usize) -> anyhow::Result<()> {
+        self.stop();
+        let (table_capacity, tree_capacity) = hash_budget(backend, hash_capacity);
+        let tt = TranspositionTable::new(table_capacity)?;
+        self.tt = Arc::new(tt);
+        self.search_tree().set_hash_capacity(tree_capacity);
+        self.config.backend = backend;
+        self.config.hash_capacity = hash_capacity;
+        Ok(())
+    }
+
+    pub fn clear_hash(&mut self) {
+        self.stop();
+        self.tt.clear();
+    }
+
+    #[cfg(test)]
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+
+    /// Set up the position of a `position` command. When it only adds moves to the current game
+    /// the search state is kept and advanced, otherwise the engine is reset.
+    pub fn set_position(&mut self, position: &PositionCommand) -> Result<(), UciError> {
+        let game = position.to_game()?;
+        self.stop();
+        match self.game.history().continued_by(game.history()) {
+            Some(moves) => self.search_tree().advance(moves),
+            None => self.reset(game.board().clone()),
+        }
+        self.game = game;
+        Ok(())
+    }
+
+    /// Start searching the current position on a worker thread, which reports every iteration and
+    /// then the best move. Mate searches prove a mate within the number of moves with [mate::search],
+    /// and only search normally to pick a move when there is none. Infinite ones
+    /// run to the maximum depth and hold the best move back until [Engine::stop]. Ponder searches
+    /// hold it back until [Engine::stop] or [Engine::ponderhit], and their clock only starts on
+    /// ponderhit. `go perft` counts the moves instead of searching.
+    pub fn go(&mut self, go: &GoCommand) -> Result<(), UciError> {
+        let search_moves = self.search_moves(go)?;
+        self.stop();
+        if let Some(GoKind::Perft(depth)) = go.kind {
+            self.divide(depth, &search_moves);
+            return Ok(());
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let ponder = Arc::new(AtomicBool::new(go.params.contains(&GoParam::Ponder)));
+        let mut limits = match go.kind {
+            Some(GoKind::Depth(depth)) => SearchLimits { depth: Some(depth), ..Default::default() },
+            Some(GoKind::Nodes(nodes)) => SearchLimits { nodes: Some(nodes as u64), ..Default::default() },
+            // Only used without a mate. Mates on the horizon are not seen, so the last move needs
+            // an extra ply.
+            Some(GoKind::Mate(moves)) => SearchLimits { depth: Some(moves.saturating_mul(2)), ..Default::default() },
+            Some(GoKind::Infinite) | None => SearchLimits::default(),
+            Some(GoKind::Perft(_)) => unreachable!("perft is not a search"),
+        };
+        let infinite = go.kind == Some(GoKind::Infinite);
+        let mate_moves = match go.kind {
+            Some(GoKind::Mate(moves)) => Some(moves),
+            _ => None,
+        };
+        if !infinite {
+            let overhead = Duration::from_millis(self.config.move_overhead as u64);
+            if let Some(time) = self.time_control(go).limits(overhead) {
+                limits.soft_time = Some(time.soft);
+                limits.time = Some(time.hard);
+            }
+        }
+        limits.stop = Some(stop.clone());
+        limits.ponder = Some(ponder.clone());
+        limits.multi_pv = self.config.multi_pv;
+        limits.search_moves = search_moves;
+        let multi_pv = self.config.multi_pv > 1;
+        let show_wdl = self.config.show_wdl;
+        let backend = self.config.backend;
+        let threads = self.config.num_threads;
+        let search_tree = self.search_tree.clone();
+        let tt = self.tt.clone();
+        let handle = thread::Builder::new().name("search".to_string()).spawn(move || {
+            let report = |result: &SearchResult| {
+                let mut info = info_event(result);
+                if multi_pv {
+                    info = info.multi_pv(result.multi_pv);
+                }
+                if show_wdl {
+                    info = info.wdl(wdl(result));
+                }
+                if backend == Backend::AlphaBeta {
+                    info = info.hash_full(tt.hashfull());
+                }
+                println!("{}", UciEvent::Info(Box::new(info)));
+            };
+            let mut tree = search_tree.lock().expect("search tree lock poisoned");
+            // Mate searches fall back to the backend to pick a move when there is no mate.
+            let mate = mate_moves.and_then(|moves| match mate::search(&tree.root, moves, limits.clone()) {
+                MateSearch::Mate(result) => Some(result),
+                MateSearch::NoMate => {
+                    println!("{}", UciEvent::Info(Box::new(InfoEvent::default().string(format!("no mate in {moves}")))));
+                    None
+                },
+                MateSearch::Aborted => None,
+            });
+            let result = match (mate, backend) {
+                (Some(result), _) => {
+                    report(&result);
+                    result
+                },
+                (None, Backend::AlphaBeta) => alphabeta::search_with(&tree.root, limits.clone(), &tt, threads, report),
+                (None, Backend::Mcts) => mcts::search_with(&mut tree, limits.clone(), threads, report),
+            };
+            tree.current_depth = result.depth as u16;
+            drop(tree);
+            while (infinite || limits.is_pondering()) && !limits.is_stopped() {
+                thread::park();
+            }
+            let ponder = result.pv.get(1).map(|m| m.to_string());
+            println!("{}", UciEvent::BestMove { best: result.best.to_string(), ponder });
+        }).expect("failed to spawn search thread");
+        self.searching = Some(Searching::new(stop, ponder, handle));
+        Ok(())
+    }
+
+    /// Leaf nodes below each root move at the depth, and their total, like `go perft` in other
+    /// engines. Only the `searchmoves` are counted when there are any.
+    fn divide(&self, depth: usize, search_moves: &[ShortMove]) {
+        let board = self.game.board();
+        let mut total = 0;
+        for m in generate_legal_moves(board) {
+            if !search_moves.is_empty() && !search_moves.contains(&m) {
+                continue;
+            }
+            let mut next = board.clone();
+            next.make_move(m);
+            let nodes = perft(&next, depth.saturating_sub(1));
+            total += nodes;
+            println!("{m}: {nodes}");
+        }
+        println!();
+        println!("Nodes searched: {total}");
+    }
+
+    /// The board, its FEN, hash key and the pieces giving check, for `d`.
+    pub fn print_position(&self) {
+        let board = self.game.board();
+        let checkers = bitboard_square_iter(board.checkers()).map(|square| square.name()).collect::<Vec<_>>();
+        println!("{board}");
+        println!("Fen: {}", board.as_fen());
+        println!("Key: {:016X}", board.hash());
+        println!("Checkers: {}", checkers.join(" "));
+    }
+
+    /// The static evaluation term by term from White's side, for `eval`. Totals are tapered by the
+    /// game phase.
+    pub fn print_eval(&self) {
+        let board = self.game.board();
+        let phase = Evaluation::new(board).phase();
+        println!("{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6}", "Term", "White", "", "Black", "", "Total");
+        println!("{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6}", "", "MG", "EG", "MG", "EG", "");
+        for term in evaluation_terms(board) {
+            let (white, black) = (term.white, term.black);
+            println!("{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6}", term.name, white.mg, white.eg, black.mg, black.eg, (white - black).taper(phase));
+        }
+        println!("{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6}", "Total", "", "", "", "", Evaluation::new(board).score(Side::White));
+        println!();
+        println!("Phase: {phase}");
+        if let Some(score) = kpk_score(board) {
+            println!("KPK bitbase: {score}");
+        }
+        println!("Evaluation: {} cp for {:?}", evaluate(board), board.active_side());
+    }
+
+    /// Swap the sides of the current position, which starts a new game from it.
+    pub fn flip(&mut self) {
+        self.reset(self.game.board().mirrored());
+    }
+
+    /// Root moves of `go searchmoves`, each must be legal in the current position.
+    fn search_moves(&self, go: &GoCommand) -> Result<Vec<ShortMove>, UciError> {
+        let notations = go.params.iter().flat_map(|param| match param {
+            GoParam::SearchMoves(moves) => moves.as_slice(),
+            _ => &[],
+        });
+        notations.map(|notation| self.game.board().parse_uci_move(notation).map_err(|_| UciError::IllegalMove(notation.clone()))).collect()
+    }
+
+    /// Clock of the side to move from the `go` parameters.
+    fn time_control(&self, go: &GoCommand) -> TimeControl {
+        let white = self.game.board().active_side() == Side::White;
+        let ms = |n: &usize| Duration::from_millis(*n as u64);
+        let mut control = TimeControl::default();
+        for param in go.params.iter() {
+            match param {
+                GoParam::WTime(n) if white => control.time = Some(ms(n)),
+                GoParam::BTime(n) if !white => control.time = Some(ms(n)),
+                GoParam::WInc(n) if white => control.increment = ms(n),
+                GoParam::BInc(n) if !white => control.increment = ms(n),
+                GoParam::MovesToGo(n) => control.moves_to_go = Some(*n as u32),
+                GoParam::MoveTime(n) => control.move_time = Some(ms(n)),
+                _ => {},
+            }
+        }
+        control
+    }
+
+    #[cfg(test)]
+    pub fn is_searching(&self) -> bool {
+        self.searching.as_ref().is_some_and(|searching| !searching.is_finished())
+    }
+
+    /// The opponent played the move that was pondered on, the search goes on with its time limits.
+    pub fn ponderhit(&mut self) {
+        if let Some(searching) = self.searching.as_ref() {
+            searching.ponderhit();
         }
-        self.search_tree = SearchTree::new(board); // TODO: can any of the tree be saved?
     }
 
-    /// Stops any ongoing search and returns (best move, ponder move).
-    pub fn stop(&mut self) -> (String, Option<String>) {
-        todo!()
+    /// Stops any ongoing search, which sends its best move, and waits for the thread to finish.
+    pub fn stop(&mut self) {
+        if let Some(searching) = self.searching.take() {
+            searching.stop();
+        }
     }
 }
+
+fn info_event(result: &SearchResult) -> InfoEvent {
+    let score = match result.mate_in() {
+        Some(mate) => ScoreEvent::mate(mate),
+        None => ScoreEvent::cp(result.score),
+    };
+    InfoEvent::default()
+        .depth(result.depth)
+        .time(result.time.as_millis() as usize)
+        .nodes(result.nodes as usize)
+        .nps(result.nps() as usize)
+        .score(score)
+        .pv(result.pv.iter())
+}
+
+/// Win, draw and loss chances of a score, from a logistic model of the centipawns. A pawn and a
+/// half up wins about a third of the games.
+fn wdl(result: &SearchResult) -> WdlEvent {
+    match result.mate_in() {
+        Some(mate) if mate > 0 => WdlEvent { win: 1000, draw: 0, loss: 0 },
+        Some(_) => WdlEvent { win: 0, draw: 0, loss: 1000 },
+        None => {
+            let chance = |cp: i32| (1000.0 / (1.0 + ((WDL_OFFSET - cp) as f64 / WDL_SCALE).exp())).round() as usize;
+            let (win, loss) = (chance(result.score), chance(-result.score));
+            WdlEvent { win, draw: 1000 - win - loss, loss }
+        },
+    }
+}
+
+/// Split of the `Hash` MB into the transposition table and the MCTS arena. Only alpha-beta uses the
+/// table and only MCTS the tree, so the backend in use gets all of it and the other is kept at its
+/// smallest, which keeps the memory used at about the setting.
+fn hash_budget(backend: Backend, hash_capacity: usize) -> (usize, usize) {
+    match backend {
+        Backend::AlphaBeta => (hash_capacity, 0),
+        Backend::Mcts => (0, hash_capacity),
+    }
+}
+
+#[test]
+fn position_continues_game() {
+    let mut engine = Engine::new(EngineConfig::default());
+    let position = |line: &str| match chess_uci::UciCommand::parse(line) {
+        Ok(chess_uci::UciCommand::Position(position)) => position,
+        other => panic!("expected a position command, got {other:?}"),
+    };
+    engine.set_position(&position("position startpos moves e2e4")).expect("e2e4 is legal");
+    engine.search_tree().current_depth = 5;
+    engine.set_position(&position("position startpos moves e2e4 e7e5 g1f3")).expect("moves are legal");
+    assert_eq!(engine.search_tree().current_depth, 5, "continuation should keep the search state");
+    assert_eq!(engine.game().history().moves().len(), 3);
+    assert_eq!(engine.search_tree().root.as_fen(), engine.game().board().as_fen());
+
+    engine.set_position(&position("position fen 8/8/8/8/8/4K3/8/4k3 b - - moves e1d1")).expect("e1d1 is legal");
+    assert_eq!(engine.search_tree().current_depth, 0, "a new position should reset the search");
+    assert_eq!(engine.game().board().as_fen(), "8/8/8/8/8/4K3/8/3k4 w - - 1 2");
+
+    assert!(matches!(engine.set_position(&position("position startpos moves e2e5")), Err(UciError::IllegalMove(m)) if m == "e2e5"));
+    assert!(matches!(engine.set_position(&position("position fen 8/8/8 w - - 0 1")), Err(UciError::InvalidFen(_))));
+    assert_eq!(engine.game().board().as_fen(), "8/8/8/8/8/4K3/8/3k4 w - - 1 2", "failed commands keep the game");
+}
+
+#[test]
+fn stop_ends_infinite_search() {
+    let mut engine = Engine::new(EngineConfig::default());
+    match chess_uci::UciCommand::parse("go infinite") {
+        Ok(chess_uci::UciCommand::Go(go)) => engine.go(&go).expect("go is valid"),
+        other => panic!("expected a go command, got {other:?}"),
+    }
+    thread::sleep(std::time::Duration::from_millis(50));
+    assert!(engine.is_searching());
+    let start = std::time::Instant::now();
+    engine.stop();
+    assert!(start.elapsed() < std::time::Duration::from_secs(1), "stop should return promptly");
+    assert!(!engine.is_searching());
+}
+
+#[test]
+fn go_uses_the_clock() {
+    let mut engine = Engine::new(EngineConfig::default());
+    let start = std::time::Instant::now();
+    match chess_uci::UciCommand::parse("go wtime 2000 btime 10 winc 0 binc 0") {
+        Ok(chess_uci::UciCommand::Go(go)) => {
+            assert_eq!(go.kind, None);
+            assert_eq!(engine.time_control(&go).time, Some(Duration::from_millis(2000)));
+            engine.go(&go).expect("go is valid");
+        },
+        other => panic!("expected a go command, got {other:?}"),
+    }
+    while engine.is_searching() {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(start.elapsed() < Duration::from_millis(1500), "the search should stay within the hard limit");
+}
+
+#[test]
+fn ponderhit_starts_the_clock() {
+    let mut engine = Engine::new(EngineConfig::default());
+    match chess_uci::UciCommand::parse("go ponder wtime 200 btime 200") {
+        Ok(chess_uci::UciCommand::Go(go)) => engine.go(&go).expect("go is valid"),
+        other => panic!("expected a go command, got {other:?}"),
+    }
+    thread::sleep(Duration::from_millis(300));
+    assert!(engine.is_searching(), "pondering ignores the clock");
+    let start = std::time::Instant::now();
+    engine.ponderhit();
+    while engine.is_searching() {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(start.elapsed() < Duration::from_millis(500), "the search should stop within its time after ponderhit");
+}
+
+#[test]
+fn failed_hash_resize_keeps_the_table() {
+    let mut engine = Engine::new(EngineConfig::default());
+    let board = Board::default();
+    engine.tt.store(board.hash(), 0, crate::search::tt::TableEntry { best: ShortMove::INVALID, score: 10, depth: 1, bound: crate::search::tt::Bound::Exact });
+    assert!(engine.set_hash_capacity(usize::MAX).is_err());
+    assert_eq!(engine.config.hash_capacity, DEFAULT_HASH_CAPACITY);
+    assert!(engine.tt.probe(board.hash(), 0).is_some(), "the old table and its entries are kept");
+}
+
+#[test]
+fn hash_goes_to_the_backend_in_use() {
+    let mut engine = Engine::new(EngineConfig { hash_capacity: 4, ..Default::default() });
+    let table_len = engine.tt.len();
+    let tree_capacity = engine.search_tree().capacity();
+    engine.set_backend(Backend::Mcts).expect("the table shrinks");
+    assert_eq!(engine.tt.len(), 1, "MCTS does not use the table");
+    assert!(engine.search_tree().capacity() > tree_capacity);
+    engine.set_hash_capacity(8).expect("8 MB fits");
+    assert_eq!(engine.tt.len(), 1);
+    engine.set_backend(Backend::AlphaBeta).expect("4 MB fits");
+    assert_eq!(engine.tt.len(), 2 * table_len);
+    assert_eq!(engine.search_tree().capacity(), tree_capacity, "alpha-beta does not use the tree");
+}
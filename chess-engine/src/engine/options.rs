@@ -0,0 +1,206 @@
+use anyhow::{anyhow, bail};
+use chess_core::moves::MAX_LEGAL_MOVES;
+use chess_uci::{Check, Combo, OptionEvent, OptionKind, Spin, UciOption};
+
+use crate::search::Backend;
+
+use super::{Engine, EngineConfig};
+
+/// Default size of hash memory in MB. It goes to the table of alpha-beta or the tree of MCTS,
+/// whichever `Search` uses, so the two together stay within the `Hash` setting.
+pub const DEFAULT_HASH_CAPACITY: usize = 512;
+const MAX_HASH_CAPACITY: usize = 1 << 15;
+pub const DEFAULT_ENABLE_PONDER: bool = false;
+pub const DEFAULT_NUM_THREADS: usize = 1;
+pub const DEFAULT_MULTI_PV: usize = 1;
+/// Default time in ms kept back per move for network and GUI latency.
+pub const DEFAULT_MOVE_OVERHEAD: usize = 30;
+const MAX_MOVE_OVERHEAD: usize = 5000;
+pub const DEFAULT_SHOW_WDL: bool = false;
+pub const DEFAULT_DEBUG_COMMANDS: bool = false;
+
+/// Callback handing a validated value to the engine.
+type Apply = fn(&mut Engine, OptionValue) -> anyhow::Result<()>;
+
+/// A `setoption` value, checked against the option's type and bounds.
+#[derive(Debug, PartialEq)]
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    /// One of the combo's vars, as the engine spells it.
+    Combo(String),
+    Button,
+    String(String),
+}
+
+impl OptionValue {
+    fn number(&self) -> usize {
+        match self {
+            OptionValue::Spin(n) => *n as usize,
+            _ => unreachable!("only spins have numbers"),
+        }
+    }
+
+    fn check(&self) -> bool {
+        matches!(self, OptionValue::Check(true))
+    }
+
+    fn text(&self) -> &str {
+        match self {
+            OptionValue::Combo(s) | OptionValue::String(s) => s,
+            _ => "",
+        }
+    }
+}
+
+/// A setting reported to `uci`. Setting it validates the value and hands it to the callback.
+pub struct EngineOption {
+    pub name: &'static str,
+    /// Type, default and bounds.
+    pub kind: OptionKind,
+    apply: Apply,
+}
+
+impl EngineOption {
+    fn new(name: &'static str, kind: OptionKind, apply: Apply) -> Self {
+        Self { name, kind, apply }
+    }
+
+    fn spin(name: &'static str, default: usize, min: usize, max: usize, apply: Apply) -> Self {
+        Self::new(name, OptionKind::Spin(Spin { default: default as i64, min: min as i64, max: max as i64 }), apply)
+    }
+
+    fn check(name: &'static str, default: bool, apply: Apply) -> Self {
+        Self::new(name, OptionKind::Check(Check { default }), apply)
+    }
+
+    pub fn event(&self) -> OptionEvent {
+        OptionEvent::new(self.name, self.kind.clone())
+    }
+
+    /// Check a `setoption` value against the type and bounds.
+    pub fn parse(&self, value: Option<&str>) -> anyhow::Result<OptionValue> {
+        let name = self.name;
+        if let OptionKind::Button = self.kind {
+            return match value {
+                None => Ok(OptionValue::Button),
+                Some(_) => Err(anyhow!("option {name} is a button and takes no value")),
+            };
+        }
+        let value = value.ok_or_else(|| anyhow!("option {name} needs a value"))?;
+        Ok(match &self.kind {
+            OptionKind::Check(_) => match value {
+                "true" => OptionValue::Check(true),
+                "false" => OptionValue::Check(false),
+                _ => bail!("option {name} must be true or false, got {value}"),
+            },
+            OptionKind::Spin(Spin { min, max, .. }) => {
+                let n = value.parse::<i64>().map_err(|_| anyhow!("option {name} must be a whole number, got {value}"))?;
+                if !(*min..=*max).contains(&n) {
+                    bail!("option {name} must be from {min} to {max}, got {n}");
+                }
+                OptionValue::Spin(n)
+            },
+            OptionKind::Combo(Combo { vars, .. }) => match vars.iter().find(|var| var.eq_ignore_ascii_case(value)) {
+                Some(var) => OptionValue::Combo(var.clone()),
+                None => bail!("option {name} must be one of {}, got {value}", vars.join(", ")),
+            },
+            OptionKind::String(_) if value == "<empty>" => OptionValue::String(String::new()),
+            OptionKind::String(_) => OptionValue::String(value.to_string()),
+            OptionKind::Button => unreachable!("buttons are handled above"),
+        })
+    }
+}
+
+/// Every option the engine supports, in the order `uci` lists them.
+pub struct OptionRegistry {
+    options: Vec<EngineOption>,
+}
+
+impl OptionRegistry {
+    pub fn new(config: &EngineConfig) -> Self {
+        let options = vec![
+            EngineOption::spin("Threads", DEFAULT_NUM_THREADS, 1, config.max_threads, |engine, value| {
+                engine.config.num_threads = value.number();
+                Ok(())
+            }),
+            // Only the backend in use holds the memory, see [DEFAULT_HASH_CAPACITY].
+            EngineOption::spin("Hash", DEFAULT_HASH_CAPACITY, 1, MAX_HASH_CAPACITY, |engine, value| {
+                engine.set_hash_capacity(value.number())
+            }),
+            EngineOption::new("Clear Hash", OptionKind::Button, |engine, _| {
+                engine.clear_hash();
+                Ok(())
+            }),
+            EngineOption::check("Ponder", DEFAULT_ENABLE_PONDER, |engine, value| {
+                engine.config.enable_ponder = value.check();
+                Ok(())
+            }),
+            EngineOption::spin("MultiPV", DEFAULT_MULTI_PV, 1, MAX_LEGAL_MOVES, |engine, value| {
+                engine.config.multi_pv = value.number();
+                Ok(())
+            }),
+            EngineOption::check("UCI_AnalyseMode", false, |engine, value| {
+                engine.config.enable_analyse = value.check();
+                Ok(())
+            }),
+            EngineOption::check("UCI_ShowWDL", DEFAULT_SHOW_WDL, |engine, value| {
+                engine.config.show_wdl = value.check();
+                Ok(())
+            }),
+            EngineOption::new("Search", OptionKind::Combo(Combo {
+                default: Backend::default().name().to_string(),
+                vars: Backend::NAMES.iter().map(|name| name.to_string()).collect(),
+            }), |engine, value| {
+                engine.set_backend(Backend::from_name(value.text()).expect("combo values are backend names"))
+            }),
+            EngineOption::spin("Move Overhead", DEFAULT_MOVE_OVERHEAD, 0, MAX_MOVE_OVERHEAD, |engine, value| {
+                engine.config.move_overhead = value.number();
+                Ok(())
+            }),
+            EngineOption::check("Debug Commands", DEFAULT_DEBUG_COMMANDS, |engine, value| {
+                engine.config.debug_commands = value.check();
+                Ok(())
+            }),
+        ];
+        Self { options }
+    }
+
+    pub fn options(&self) -> &[EngineOption] {
+        &self.options
+    }
+
+    /// Option names are not case sensitive.
+    pub fn get(&self, name: &str) -> Option<&EngineOption> {
+        self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Validate a `setoption` command, returning the callback to apply it with.
+    pub fn parse(&self, option: &UciOption) -> anyhow::Result<(Apply, OptionValue)> {
+        let engine_option = self.get(&option.name).ok_or_else(|| anyhow!("unknown option {}", option.name))?;
+        Ok((engine_option.apply, engine_option.parse(option.value.as_deref())?))
+    }
+}
+
+#[test]
+fn options_are_validated() {
+    let registry = OptionRegistry::new(&EngineConfig::default());
+    let parse = |name: &str, value: Option<&str>| registry.parse(&UciOption::new(name, value)).map(|(_, value)| value).map_err(|e| e.to_string());
+    assert_eq!(parse("move overhead", Some("100")), Ok(OptionValue::Spin(100)));
+    assert_eq!(parse("Move Overhead", Some("6000")), Err("option Move Overhead must be from 0 to 5000, got 6000".to_string()));
+    assert_eq!(parse("Hash", Some("lots")), Err("option Hash must be a whole number, got lots".to_string()));
+    assert_eq!(parse("Ponder", Some("yes")), Err("option Ponder must be true or false, got yes".to_string()));
+    assert_eq!(parse("Search", Some("mcts")), Ok(OptionValue::Combo("MCTS".to_string())));
+    assert_eq!(parse("Search", Some("random")), Err("option Search must be one of AlphaBeta, MCTS, got random".to_string()));
+    assert_eq!(parse("Clear Hash", None), Ok(OptionValue::Button));
+    assert_eq!(parse("Threads", None), Err("option Threads needs a value".to_string()));
+    assert_eq!(parse("SyzygyPath", Some("/tb")), Err("unknown option SyzygyPath".to_string()));
+
+    let path = EngineOption::new("SyzygyPath", OptionKind::String(chess_uci::Text { default: String::new() }), |_, _| Ok(()));
+    assert_eq!(path.parse(Some("my tables/wdl")).ok(), Some(OptionValue::String("my tables/wdl".to_string())));
+    assert_eq!(path.parse(Some("<empty>")).ok(), Some(OptionValue::String(String::new())));
+
+    let mut engine = Engine::new(EngineConfig::default());
+    engine.set_option(&UciOption::new("multipv", Some(3))).expect("MultiPV is an option");
+    assert_eq!(engine.config.multi_pv, 3);
+}
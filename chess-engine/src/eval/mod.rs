@@ -0,0 +1,286 @@
+//! Static evaluation, PeSTO style: every piece is worth a midgame and an endgame value from its
+//! piece-square table, and the two are blended by how much material is left.
+
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use chess_core::{board::{bitboard_square_iter, piece::{Piece, Side, NUM_PIECE_KINDS, NUM_PIECE_SIDES}, square::Square, Board}, moves::ShortMove, tablebase::kpk_probe};
+
+use pesto::{EG_TABLES, EG_VALUES, MG_TABLES, MG_VALUES, PHASE_WEIGHTS};
+
+mod pesto;
+
+/// Game phase of the starting material, where the score is all midgame.
+pub const MAX_PHASE: i32 = 24;
+/// Added to the piece-square score of won king and pawn versus king positions. Small enough that
+/// the queen is worth more, so the pawn is still pushed and promoted.
+const KPK_WIN_BONUS: i32 = 400;
+
+/// A midgame and an endgame value in centipawns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    /// Blend the values by the phase, from all endgame at 0 to all midgame at [MAX_PHASE].
+    pub fn taper(&self, phase: i32) -> i32 {
+        let phase = phase.min(MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+
+    /// Material and placement of a piece on the square.
+    fn piece_square(side: Side, piece: Piece, square: Square) -> Self {
+        // The tables start at a8, which is where a1 is from Black's side.
+        let index = match side {
+            Side::White => *square as usize ^ 56,
+            Side::Black => *square as usize,
+        };
+        let piece = piece as usize;
+        Self { mg: MG_VALUES[piece] + MG_TABLES[piece][index], eg: EG_VALUES[piece] + EG_TABLES[piece][index] }
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, rhs: Score) -> Score {
+        Score { mg: self.mg + rhs.mg, eg: self.eg + rhs.eg }
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, rhs: Score) -> Score {
+        Score { mg: self.mg - rhs.mg, eg: self.eg - rhs.eg }
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Score) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, rhs: Score) {
+        *self = *self - rhs;
+    }
+}
+
+/// Piece-square score of a position from White's side and its game phase. Both only change with
+/// the pieces that move, so [Evaluation::make_move] keeps them up to date without a rescan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Evaluation {
+    score: Score,
+    phase: i32,
+}
+
+impl Evaluation {
+    pub fn new(board: &Board) -> Self {
+        let mut eval = Self::default();
+        for side in 0..NUM_PIECE_SIDES {
+            for piece in 0..NUM_PIECE_KINDS {
+                for square in bitboard_square_iter(board.piece(side.into(), piece.into())) {
+                    eval.add(side.into(), piece.into(), square);
+                }
+            }
+        }
+        eval
+    }
+
+    /// Material left, the starting position is [MAX_PHASE]. Early promotions can go past it.
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// Tapered score in centipawns for the side.
+    pub fn score(&self, side: Side) -> i32 {
+        let white = self.score.taper(self.phase);
+        match side {
+            Side::White => white,
+            Side::Black => -white,
+        }
+    }
+
+    /// Material value of the piece at this phase, without its placement.
+    pub fn piece_value(&self, piece: Piece) -> i32 {
+        Score { mg: MG_VALUES[piece as usize], eg: EG_VALUES[piece as usize] }.taper(self.phase)
+    }
+
+    fn add(&mut self, side: Side, piece: Piece, square: Square) {
+        match side {
+            Side::White => self.score += Score::piece_square(side, piece, square),
+            Side::Black => self.score -= Score::piece_square(side, piece, square),
+        }
+        self.phase += PHASE_WEIGHTS[piece as usize];
+    }
+
+    fn remove(&mut self, side: Side, piece: Piece, square: Square) {
+        match side {
+            Side::White => self.score -= Score::piece_square(side, piece, square),
+            Side::Black => self.score += Score::piece_square(side, piece, square),
+        }
+        self.phase -= PHASE_WEIGHTS[piece as usize];
+    }
+
+    /// Update the evaluation for the move, given the board before it is made. Mirrors
+    /// [Board::make_move].
+    pub fn make_move(&mut self, board: &Board, m: ShortMove) {
+        let side = board.active_side();
+        let (src, dest) = (m.src(), m.dest());
+        let (_, piece) = board.square(src).expect("can not move from empty space");
+        if m.is_en_pasant() {
+            let captured = match side {
+                Side::White => Square::from(*dest - 8),
+                Side::Black => Square::from(*dest + 8),
+            };
+            self.remove(side.other(), Piece::Pawn, captured);
+        } else if m.is_capturing() {
+            let (_, captured) = board.square(dest).expect("can not capture empty space");
+            self.remove(side.other(), captured, dest);
+        }
+        if m.is_castling() {
+            let (rook_src, rook_dest) = if *dest > *src {
+                (Square::from(*src + 3), Square::from(*src + 1))
+            } else {
+                (Square::from(*src - 4), Square::from(*src - 1))
+            };
+            self.remove(side, Piece::Rook, rook_src);
+            self.add(side, Piece::Rook, rook_dest);
+        }
+        self.remove(side, piece, src);
+        self.add(side, m.promoted().unwrap_or(piece), dest);
+    }
+
+    /// The evaluation after the move, given the board before it.
+    pub fn after(&self, board: &Board, m: ShortMove) -> Self {
+        let mut eval = *self;
+        eval.make_move(board, m);
+        eval
+    }
+
+    /// Static evaluation of the board this was kept up to date with, see [evaluate].
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let white = match kpk_score(board) {
+            Some(score) => score,
+            None => self.score(Side::White),
+        };
+        match board.active_side() {
+            Side::White => white,
+            Side::Black => -white,
+        }
+    }
+}
+
+/// Static evaluation in centipawns from the side to move's perspective. King and pawn versus
+/// king positions are scored by the bitbase instead. Searches keep an [Evaluation] up to date
+/// through their moves rather than calling this.
+pub fn evaluate(board: &Board) -> i32 {
+    Evaluation::new(board).evaluate(board)
+}
+
+/// Score of king and pawn versus king positions from White's side, see [kpk_probe]. Draws are 0,
+/// wins keep the piece-square score, which grows as the pawn advances, plus a bonus.
+pub fn kpk_score(board: &Board) -> Option<i32> {
+    let won = kpk_probe(board)?;
+    let strong = if board.piece(Side::White, Piece::Pawn) != 0 { 1 } else { -1 };
+    Some(if won { Evaluation::new(board).score(Side::White) + strong * KPK_WIN_BONUS } else { 0 })
+}
+
+/// One part of [Evaluation], what it is worth to each side.
+pub struct EvalTerm {
+    pub name: &'static str,
+    pub white: Score,
+    pub black: Score,
+}
+
+/// The piece-square scores of each kind of piece, which add up to [Evaluation], for the `eval`
+/// command.
+pub fn evaluation_terms(board: &Board) -> Vec<EvalTerm> {
+    const NAMES: [&str; NUM_PIECE_KINDS] = ["Pawns", "Knights", "Bishops", "Rooks", "Queens", "Kings"];
+    NAMES.into_iter().enumerate().map(|(piece, name)| {
+        let score = |side: Side| bitboard_square_iter(board.piece(side, piece.into()))
+            .map(|square| Score::piece_square(side, piece.into(), square))
+            .fold(Score::default(), Score::add);
+        EvalTerm { name, white: score(Side::White), black: score(Side::Black) }
+    }).collect()
+}
+
+#[test]
+fn incremental_evaluation_matches_a_rescan() {
+    use chess_core::moves::generate_legal_moves;
+
+    let fens = [
+        // Castling both ways and promotions with and without captures.
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+        // En passant.
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ];
+    for fen in fens {
+        let mut board = Board::new();
+        board.load_fen(fen).expect("failed to load fen");
+        let eval = Evaluation::new(&board);
+        for m in generate_legal_moves(&board) {
+            let mut next = board.clone();
+            next.make_move(m);
+            let mut updated = eval;
+            updated.make_move(&board, m);
+            assert_eq!(updated, Evaluation::new(&next), "{fen} {m}");
+        }
+    }
+}
+
+#[test]
+fn evaluation_is_symmetric_and_tapered() {
+    let mut board = Board::default();
+    assert_eq!(Evaluation::new(&board).phase(), MAX_PHASE);
+    assert_eq!(evaluate(&board), 0);
+
+    board.load_fen("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N2N2/PP2BPPP/R1BQ1RK1 w - - 0 8").expect("failed to load fen");
+    assert_eq!(evaluate(&board), evaluate(&board.mirrored()), "mirrored positions should score the same for the side to move");
+    let eval = Evaluation::new(&board);
+    assert_eq!(eval.score(Side::White), -eval.score(Side::Black));
+
+    // A lone rook is worth its endgame value.
+    board.load_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").expect("failed to load fen");
+    assert_eq!(Evaluation::new(&board).phase(), 2);
+    let score = Evaluation::new(&board).score;
+    assert_eq!(Evaluation::new(&board).score(Side::White), score.taper(2));
+    assert_eq!(score.taper(0), score.eg);
+    assert_eq!(score.taper(MAX_PHASE), score.mg);
+
+    board.load_fen("8/8/8/8/8/1k6/2P5/K7 b - - 0 1").expect("failed to load fen");
+    assert_eq!(kpk_score(&board), Some(0), "the bitbase scores king and pawn versus king");
+}
+
+#[test]
+fn won_pawn_endings_push_and_promote() {
+    use chess_core::moves::generate_legal_moves;
+
+    // White wins by pushing the pawn, Black by the same moves on the mirrored board.
+    for fen in ["8/8/4P3/8/8/2k5/8/4K3 w - - 0 1", "4k3/8/2K5/8/8/4p3/8/8 b - - 0 1"] {
+        let mut board = Board::new();
+        board.load_fen(fen).expect("failed to load fen");
+        for _ in 0..2 {
+            let push = generate_legal_moves(&board).into_iter()
+                .find(|m| board.square(m.src()).is_some_and(|(_, piece)| piece == Piece::Pawn) && m.promoted().is_none_or(|piece| piece == Piece::Queen))
+                .expect("the pawn can move");
+            let before = evaluate(&board);
+            assert!(before > 0, "{} is won", board.as_fen());
+            let mut next = board.clone();
+            next.make_move(push);
+            assert!(-evaluate(&next) > before, "{push} should score higher than {}", board.as_fen());
+            // Let the defender pass with a king move that keeps the win.
+            board = next;
+            let waiting = generate_legal_moves(&board).into_iter().find(|m| {
+                let mut next = board.clone();
+                next.make_move(*m);
+                evaluate(&next) > 0
+            }).expect("the defender has a move");
+            board.make_move(waiting);
+        }
+    }
+}
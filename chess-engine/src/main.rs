@@ -1,53 +1,169 @@
+use std::{io::{BufRead, Write}, sync::mpsc::{self, Sender}, thread};
+
+use anyhow::Context;
 use chess_core::board::Board;
+use chess_uci::{InfoEvent, UciCommand, UciEvent};
 use engine::{Engine, EngineConfig};
-use uci::{GoKind, UciCommand, UciEvent, UciOption};
+mod cli;
 mod engine;
+mod eval;
 mod search;
-mod uci;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args) {
+            eprintln!("error: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let reader = thread::Builder::new().name("stdin".to_string()).spawn(move || read_commands(std::io::stdin().lock(), sender)).expect("failed to spawn stdin thread");
     let config = EngineConfig::default();
     let mut engine = Engine::new(config);
-    let stdin = std::io::stdin();
-    loop {
-        let mut buf = String::new();
-        stdin.read_line(&mut buf).expect("failed to read stdin");
-        match UciCommand::parse(&buf) {
+    // Ends on quit or when stdin closes.
+    for command in receiver {
+        let command = command.and_then(|command| check_enabled(&engine.config, command));
+        let result = match command {
             Ok(UciCommand::Uci) => {
                 engine.report_about();
                 println!("{}", UciEvent::UciOk);
+                Ok(())
             },
-            Ok(UciCommand::Debug(b)) => engine.config.enable_debug = b,
+            Ok(UciCommand::Debug(b)) => {
+                engine.config.enable_debug = b;
+                Ok(())
+            },
+            // Commands are handled in order and searches run on their own thread, so by the time
+            // this is read everything before it is done.
             Ok(UciCommand::IsReady) => {
-                // TODO: ensure engine is ready
                 println!("{}", UciEvent::ReadyOk);
+                Ok(())
             },
-            Ok(UciCommand::SetOption(opt)) => match opt {
-                UciOption::Threads(n) => engine.config.num_threads = n,
-                UciOption::Hash(n) => engine.config.hash_capacity = n,
-                UciOption::Ponder(b) => engine.config.enable_ponder = b,
-                UciOption::MultiPV(n) => engine.config.multi_pv = n,
-                UciOption::UciAnalyseMode(b) => engine.config.enable_analyse = b,
-            },
-            Ok(UciCommand::Register(reg)) => todo!(), // TODO: idk if this is needed
-            Ok(UciCommand::UciNewGame) => engine.reset(Board::default()),
-            Ok(UciCommand::Position(pos)) => todo!(),
-            Ok(UciCommand::Go(go)) => match go.kind {
-                GoKind::Infinite => todo!(),
-                GoKind::Mate(m) => todo!(),
-                GoKind::Depth(d) => todo!(),
-                GoKind::Nodes(n) => todo!(),
+            Ok(UciCommand::SetOption(option)) => engine.set_option(&option),
+            // The engine is free, there is nothing to register.
+            Ok(UciCommand::Register(_)) => Ok(()),
+            Ok(UciCommand::UciNewGame) => {
+                engine.reset(Board::default());
+                Ok(())
             },
+            Ok(UciCommand::Position(pos)) => engine.set_position(&pos).map_err(anyhow::Error::from),
+            Ok(UciCommand::Go(go)) => engine.go(&go).map_err(anyhow::Error::from),
             Ok(UciCommand::Stop) => {
-                let (best, ponder) = engine.stop();
-                println!("{}", UciEvent::BestMove { best, ponder });
+                engine.stop();
+                Ok(())
+            },
+            Ok(UciCommand::PonderHit) => {
+                engine.ponderhit();
+                Ok(())
+            },
+            Ok(UciCommand::Quit) => break,
+            Ok(UciCommand::PrintBoard) => {
+                engine.print_position();
+                Ok(())
             },
-            Ok(UciCommand::PonderHit) => todo!(),
-            Ok(UciCommand::Quit) => {
-                let _ = engine.stop();
-                break;
+            Ok(UciCommand::Eval) => {
+                engine.print_eval();
+                Ok(())
             },
-            Err(e) => eprintln!("{}", e),
+            Ok(UciCommand::Flip) => {
+                engine.flip();
+                Ok(())
+            },
+            Ok(UciCommand::Bench(bench)) => {
+                engine.stop();
+                cli::bench::bench(
+                    bench.depth.unwrap_or(cli::bench::DEFAULT_DEPTH),
+                    bench.threads.unwrap_or(cli::bench::DEFAULT_THREADS),
+                    bench.hash.unwrap_or(cli::bench::DEFAULT_HASH_CAPACITY),
+                )
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            report_error(&engine.config, &e, std::io::stdout(), std::io::stderr());
         }
     }
+    engine.stop();
+    reader.join().expect("stdin thread panicked");
+}
+
+/// Reject the commands outside the protocol unless the Debug Commands option is on, so strict GUIs
+/// never see them answered.
+fn check_enabled(config: &EngineConfig, command: UciCommand) -> anyhow::Result<UciCommand> {
+    if command.is_extension() && !config.debug_commands {
+        anyhow::bail!("{command} is a debug command, turn on the Debug Commands option to use it");
+    }
+    Ok(command)
+}
+
+/// Problems with the input go to the GUI as `info string` in debug mode and to stderr otherwise,
+/// the protocol has no error messages.
+fn report_error(config: &EngineConfig, error: &anyhow::Error, mut stdout: impl Write, mut stderr: impl Write) {
+    // There is nowhere left to report a failed write to.
+    let _ = if config.enable_debug {
+        writeln!(stdout, "{}", UciEvent::Info(Box::new(InfoEvent::default().string(format!("error: {error:#}")))))
+    } else {
+        writeln!(stderr, "error: {error:#}")
+    };
+}
+
+/// Parse the input line by line onto the channel until quit or the end of input, which closes the
+/// channel. Blank lines are skipped.
+fn read_commands(input: impl BufRead, sender: Sender<anyhow::Result<UciCommand>>) {
+    for line in input.lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = UciCommand::parse(&line).with_context(|| format!("ignored {:?}", line.trim()));
+        let quit = matches!(command, Ok(UciCommand::Quit));
+        if sender.send(command).is_err() || quit {
+            break;
+        }
+    }
+}
+
+#[test]
+fn debug_commands_are_off_by_default() {
+    let mut config = EngineConfig::default();
+    for line in ["d", "eval", "flip", "bench 1", "go perft 2"] {
+        let command = UciCommand::parse(line).expect("debug commands parse");
+        let error = check_enabled(&config, command).expect_err(line);
+        assert!(error.to_string().contains("Debug Commands"), "{line}: {error}");
+    }
+    assert!(check_enabled(&config, UciCommand::parse("go depth 2").expect("go parses")).is_ok());
+
+    config.debug_commands = true;
+    assert!(matches!(check_enabled(&config, UciCommand::parse("d").expect("d parses")), Ok(UciCommand::PrintBoard)));
+}
+
+#[test]
+fn commands_are_read_until_quit_or_the_end_of_input() {
+    let read = |input: &str| {
+        let (sender, receiver) = mpsc::channel();
+        read_commands(input.as_bytes(), sender);
+        // The channel is closed, so this ends instead of waiting.
+        receiver.into_iter().map(|command| command.map_err(|e| e.to_string())).collect::<Vec<_>>()
+    };
+    assert_eq!(read("uci\n\n  \nisready"), [Ok(UciCommand::Uci), Ok(UciCommand::IsReady)]);
+    assert_eq!(read(""), []);
+    assert_eq!(read("isready\nquit\nuci\n"), [Ok(UciCommand::IsReady), Ok(UciCommand::Quit)]);
+    assert_eq!(read("joho\n"), [Err("ignored \"joho\"".to_string())]);
+}
+
+#[test]
+fn errors_go_to_the_gui_only_in_debug_mode() {
+    let mut config = EngineConfig::default();
+    let error = anyhow::anyhow!("bad input").context("ignored \"joho\"");
+    let report = |config: &EngineConfig| {
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        report_error(config, &error, &mut stdout, &mut stderr);
+        (String::from_utf8(stdout).expect("utf-8"), String::from_utf8(stderr).expect("utf-8"))
+    };
+    assert_eq!(report(&config), (String::new(), "error: ignored \"joho\": bad input\n".to_string()));
+    config.enable_debug = true;
+    assert_eq!(report(&config), ("info string error: ignored \"joho\": bad input\n".to_string(), String::new()));
 }
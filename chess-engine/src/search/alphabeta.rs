@@ -0,0 +1,589 @@
+use std::{sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc}, thread, time::{Duration, Instant}};
+
+use chess_core::{board::{piece::Piece, Board}, moves::{generate_legal_moves, ShortMove}};
+
+use crate::eval::Evaluation;
+
+use super::{time::{SearchClock, TimeLimits, TimeManager}, tt::{Bound, TableEntry, TranspositionTable}};
+
+/// Score of being mated at the root, mates further away score closer to zero.
+pub const MATE_SCORE: i32 = 32_000;
+/// Scores beyond this are mates.
+pub const MATE_BOUND: i32 = MATE_SCORE - 1_000;
+const INFINITY: i32 = MATE_SCORE + 1;
+/// Nodes searched between checks of the node and time budgets.
+const CHECK_INTERVAL: u64 = 1024;
+pub const MAX_DEPTH: usize = 64;
+/// Half width of the first aspiration window in centipawns.
+const ASPIRATION_WINDOW: i32 = 50;
+/// Depth from which iterations start with an aspiration window around the previous score.
+const ASPIRATION_DEPTH: usize = 4;
+/// Room in centipawns left for the positional gain of a capture when delta pruning quiescence.
+const DELTA_MARGIN: i32 = 200;
+/// Size in MB of the transposition table of searches that don't share one.
+const SEARCH_HASH_CAPACITY: usize = 16;
+
+/// Budget of a search. The search runs until any limit is reached, or to [MAX_DEPTH] if none are set.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    /// Hard limit, the search is cut off when it runs out.
+    pub time: Option<Duration>,
+    /// Target time, no iteration is started past it, see [TimeManager].
+    pub soft_time: Option<Duration>,
+    /// Set from another thread to end the search early.
+    pub stop: Option<Arc<AtomicBool>>,
+    /// Set while the search ponders, the time limits only apply once it is cleared.
+    pub ponder: Option<Arc<AtomicBool>>,
+    /// Number of best root moves searched with their own window, 0 counts as 1.
+    pub multi_pv: usize,
+    /// Root moves to search, as given by `go searchmoves`, all legal moves when empty.
+    pub search_moves: Vec<ShortMove>,
+}
+
+impl SearchLimits {
+    pub fn is_stopped(&self) -> bool {
+        self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.as_ref().is_some_and(|ponder| ponder.load(Ordering::Relaxed))
+    }
+
+    pub fn clock(&self) -> SearchClock {
+        SearchClock::new(self.ponder.clone())
+    }
+
+    /// Whether the root move may be searched.
+    pub fn allows(&self, m: ShortMove) -> bool {
+        self.search_moves.is_empty() || self.search_moves.contains(&m)
+    }
+
+    /// Legal moves of the root that may be searched.
+    pub fn root_moves(&self, board: &Board) -> Vec<ShortMove> {
+        generate_legal_moves(board).into_iter().filter(|m| self.allows(*m)).collect()
+    }
+
+    /// Time manager for searches with a soft limit.
+    pub fn time_manager(&self) -> Option<TimeManager> {
+        let soft = self.soft_time?;
+        Some(TimeManager::new(TimeLimits { soft, hard: self.time.unwrap_or(Duration::MAX).max(soft) }))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best: ShortMove,
+    /// Centipawns from the side to move's perspective, see [MATE_SCORE].
+    pub score: i32,
+    /// Last fully searched depth.
+    pub depth: usize,
+    pub nodes: u64,
+    pub time: Duration,
+    /// Principal variation, starting with the best move.
+    pub pv: Vec<ShortMove>,
+    /// Rank of the line among the `multi_pv` best, starting at 1.
+    pub multi_pv: usize,
+}
+
+impl SearchResult {
+    /// Moves until mate, negative when the side to move is getting mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score >= MATE_BOUND {
+            Some((MATE_SCORE - self.score + 1) / 2)
+        } else if self.score <= -MATE_BOUND {
+            Some(-(MATE_SCORE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+
+    pub fn nps(&self) -> u64 {
+        (self.nodes as u128 * 1000 / self.time.as_millis().max(1)) as u64
+    }
+}
+
+/// State shared by the threads of one search.
+struct SharedSearch {
+    clock: SearchClock,
+    /// Nodes of all threads, added in batches of [CHECK_INTERVAL].
+    nodes: AtomicU64,
+    /// Set when the main thread is done so the helpers stop too.
+    done: AtomicBool,
+}
+
+struct Searcher<'a> {
+    limits: SearchLimits,
+    tt: &'a TranspositionTable,
+    shared: &'a SharedSearch,
+    start: Instant,
+    nodes: u64,
+    /// Nodes already added to the shared count.
+    flushed: u64,
+    aborted: bool,
+    /// Triangular table, `pv[ply]` holds the best line found from that ply.
+    pv: Box<[[ShortMove; MAX_DEPTH + 1]; MAX_DEPTH + 1]>,
+    pv_len: [usize; MAX_DEPTH + 1],
+    /// Principal variation of the previous iteration, searched first.
+    prev_pv: Vec<ShortMove>,
+    follow_pv: bool,
+    /// Root moves left out, the best moves of the lines already searched this iteration.
+    excluded: Vec<ShortMove>,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(limits: SearchLimits, tt: &'a TranspositionTable, shared: &'a SharedSearch, start: Instant) -> Self {
+        Self {
+            limits,
+            tt,
+            shared,
+            start,
+            nodes: 0,
+            flushed: 0,
+            aborted: false,
+            pv: Box::new([[ShortMove::INVALID; MAX_DEPTH + 1]; MAX_DEPTH + 1]),
+            pv_len: [0; MAX_DEPTH + 1],
+            prev_pv: vec![],
+            follow_pv: false,
+            excluded: vec![],
+        }
+    }
+
+    /// Add the nodes searched since the last flush to the shared count and return the total.
+    fn flush_nodes(&mut self) -> u64 {
+        let nodes = self.nodes - self.flushed;
+        self.flushed = self.nodes;
+        self.shared.nodes.fetch_add(nodes, Ordering::Relaxed) + nodes
+    }
+
+    fn out_of_budget(&mut self) -> bool {
+        if !self.aborted && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let nodes = self.flush_nodes();
+            self.aborted = self.limits.nodes.is_some_and(|n| nodes >= n)
+                || self.limits.time.is_some_and(|t| self.shared.clock.elapsed() >= t)
+                || self.limits.is_stopped()
+                || self.shared.done.load(Ordering::Relaxed);
+        }
+        self.aborted
+    }
+
+    fn update_pv(&mut self, ply: usize, m: ShortMove) {
+        let child_len = self.pv_len[ply + 1];
+        self.pv[ply][0] = m;
+        let (head, tail) = self.pv.split_at_mut(ply + 1);
+        head[ply][1..=child_len].copy_from_slice(&tail[0][..child_len]);
+        self.pv_len[ply] = child_len + 1;
+    }
+
+    /// Search the board to the depth. `eval` is kept up to date with the board through the moves.
+    fn negamax(&mut self, board: &Board, eval: &Evaluation, depth: usize, ply: usize, mut alpha: i32, mut beta: i32) -> i32 {
+        self.pv_len[ply] = 0;
+        if depth == 0 {
+            return self.quiescence(board, eval, alpha, beta);
+        }
+        self.nodes += 1;
+        if self.out_of_budget() {
+            return 0;
+        }
+        let key = board.hash();
+        let entry = self.tt.probe(key, ply);
+        // Cutoffs are left out at the root and in PV nodes so the principal variation stays whole.
+        if let Some(entry) = entry.filter(|e| ply > 0 && beta - alpha == 1 && e.depth >= depth) {
+            match entry.bound {
+                Bound::Exact => return entry.score.clamp(alpha, beta),
+                Bound::Lower if entry.score >= beta => return beta,
+                Bound::Upper if entry.score <= alpha => return alpha,
+                _ => {}
+            }
+        }
+        let moves = generate_legal_moves(board);
+        if moves.is_empty() {
+            return if board.in_check() { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+        // Mate distance pruning, a shorter mate was already found elsewhere.
+        alpha = alpha.max(-MATE_SCORE + ply as i32);
+        beta = beta.min(MATE_SCORE - ply as i32 - 1);
+        if alpha >= beta {
+            return alpha;
+        }
+        let original_alpha = alpha;
+        let hint = if self.follow_pv { self.prev_pv.get(ply).copied() } else { None };
+        self.follow_pv = hint.is_some_and(|m| moves.contains(&m));
+        let hint = hint.filter(|_| self.follow_pv).or(entry.map(|e| e.best));
+        let mut ordered = order_moves(board, eval, moves, hint);
+        // The result of a root search without all moves must not be mistaken for the real one.
+        let store = ply > 0 || (self.excluded.is_empty() && self.limits.search_moves.is_empty());
+        if ply == 0 {
+            ordered.retain(|m| !self.excluded.contains(m) && self.limits.allows(*m));
+        }
+        let mut best = ShortMove::INVALID;
+        for (i, m) in ordered.into_iter().enumerate() {
+            let next_eval = eval.after(board, m);
+            let mut next = board.clone();
+            next.make_move(m);
+            let score = if i == 0 {
+                -self.negamax(&next, &next_eval, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                // Prove the move is worse with a null window, re-search if it is not.
+                let score = -self.negamax(&next, &next_eval, depth - 1, ply + 1, -alpha - 1, -alpha);
+                if score > alpha && score < beta {
+                    -self.negamax(&next, &next_eval, depth - 1, ply + 1, -beta, -alpha)
+                } else {
+                    score
+                }
+            };
+            self.follow_pv = false;
+            if self.aborted {
+                return 0;
+            }
+            if score > alpha {
+                // Also kept on a cutoff, mate distance pruning can make beta the exact score.
+                self.update_pv(ply, m);
+                if score >= beta {
+                    if store {
+                        self.tt.store(key, ply, TableEntry { best: m, score: beta, depth, bound: Bound::Lower });
+                    }
+                    return beta;
+                }
+                alpha = score;
+                best = m;
+            }
+        }
+        if store {
+            let bound = if alpha > original_alpha { Bound::Exact } else { Bound::Upper };
+            self.tt.store(key, ply, TableEntry { best, score: alpha, depth, bound });
+        }
+        alpha
+    }
+
+    /// Search captures until the position is quiet so material swaps are not cut off halfway.
+    fn quiescence(&mut self, board: &Board, eval: &Evaluation, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.out_of_budget() {
+            return 0;
+        }
+        let stand_pat = eval.evaluate(board);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+        let moves = generate_legal_moves(board).into_iter().filter(|m| m.is_capturing() || m.is_promotion());
+        for m in order_moves(board, eval, moves, None) {
+            // Delta pruning, not even winning the material raises alpha.
+            if stand_pat + material_gain(board, eval, m) + DELTA_MARGIN <= alpha {
+                continue;
+            }
+            let next_eval = eval.after(board, m);
+            let mut next = board.clone();
+            next.make_move(m);
+            let score = -self.quiescence(&next, &next_eval, -beta, -alpha);
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Search the root to the depth, widening an aspiration window around the previous score
+    /// until the score falls inside it. Returns `None` if the budget ran out.
+    fn iterate(&mut self, board: &Board, depth: usize, previous: i32) -> Option<i32> {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = if depth >= ASPIRATION_DEPTH && previous.abs() < MATE_BOUND {
+            (previous - delta, previous + delta)
+        } else {
+            (-INFINITY, INFINITY)
+        };
+        loop {
+            self.follow_pv = true;
+            let score = self.negamax(board, &Evaluation::new(board), depth, 0, alpha, beta);
+            if self.aborted {
+                return None;
+            }
+            if score <= alpha && alpha > -INFINITY {
+                alpha = (alpha - delta).max(-INFINITY);
+            } else if score >= beta && beta < INFINITY {
+                beta = (beta + delta).min(INFINITY);
+            } else {
+                return Some(score);
+            }
+            delta *= 2;
+        }
+    }
+}
+
+/// Material won by a capture or promotion: the captured piece plus what the pawn becomes over
+/// its own value.
+fn material_gain(board: &Board, eval: &Evaluation, m: ShortMove) -> i32 {
+    let victim = if m.is_en_pasant() {
+        eval.piece_value(Piece::Pawn)
+    } else {
+        board.square(m.dest()).map_or(0, |(_, piece)| eval.piece_value(piece))
+    };
+    victim + m.promoted().map_or(0, |piece| eval.piece_value(piece) - eval.piece_value(Piece::Pawn))
+}
+
+/// The previous best move first, then captures and promotions by MVV-LVA, the most valuable victim
+/// taken by the least valuable attacker, then quiet moves.
+fn order_moves(board: &Board, eval: &Evaluation, moves: impl IntoIterator<Item = ShortMove>, best: Option<ShortMove>) -> Vec<ShortMove> {
+    let mut ordered: Vec<ShortMove> = moves.into_iter().collect();
+    ordered.sort_by_cached_key(|m| {
+        if Some(*m) == best {
+            (0, 0, 0)
+        } else if m.is_capturing() || m.is_promotion() {
+            let attacker = board.square(m.src()).map_or(0, |(_, piece)| eval.piece_value(piece));
+            (1, -material_gain(board, eval, *m), attacker)
+        } else {
+            (2, 0, 0)
+        }
+    });
+    ordered
+}
+
+/// Find the best move with an iterative deepening principal variation search.
+pub fn search(board: &Board, limits: SearchLimits) -> SearchResult {
+    search_with(board, limits, &TranspositionTable::new(SEARCH_HASH_CAPACITY).expect("failed to allocate the hash table"), 1, |_| {})
+}
+
+/// Like [search], sharing the transposition table and calling back with the result of every
+/// completed iteration of the main thread.
+///
+/// With more than one thread the search runs Lazy SMP: helper threads search the same position
+/// and only cooperate through the table, every other one a ply deeper so the threads spread over
+/// more depths. Once the main thread is done the threads vote on the best move, see [vote].
+pub fn search_with(board: &Board, limits: SearchLimits, tt: &TranspositionTable, threads: usize, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+    let moves = limits.root_moves(board);
+    let first = moves.first().copied().unwrap_or(ShortMove::INVALID);
+    if moves.is_empty() {
+        return SearchResult {
+            best: first,
+            score: if board.in_check() { -MATE_SCORE } else { 0 },
+            depth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: vec![],
+            multi_pv: 1,
+        };
+    }
+    tt.new_search();
+    let shared = SharedSearch { clock: limits.clock(), nodes: AtomicU64::new(0), done: AtomicBool::new(false) };
+    let start = Instant::now();
+    let mut results = thread::scope(|scope| {
+        let helpers: Vec<_> = (1..threads.max(1)).map(|id| {
+            let mut searcher = Searcher::new(limits.clone(), tt, &shared, start);
+            scope.spawn(move || searcher.deepen(board, first, 1 + id % 2, None, |_| {}))
+        }).collect();
+        let mut searcher = Searcher::new(limits.clone(), tt, &shared, start);
+        let main = searcher.deepen(board, first, 1, limits.time_manager(), &mut on_iteration);
+        shared.done.store(true, Ordering::Relaxed);
+        let mut results = vec![main];
+        results.extend(helpers.into_iter().map(|helper| helper.join().expect("search thread panicked")));
+        results
+    });
+    let main_best = results[0].best;
+    // The lines of a MultiPV search are only known to the main thread.
+    let mut result = if limits.multi_pv > 1 { results.swap_remove(0) } else { vote(results) };
+    result.nodes = shared.nodes.load(Ordering::Relaxed);
+    result.time = start.elapsed();
+    if result.best != main_best {
+        on_iteration(&result);
+    }
+    result
+}
+
+/// Pick the result of the search threads to play. Every thread votes for its best move weighted
+/// by depth and by how much better its score is than the worst one, a proven mate wins outright.
+/// Ties go to the main thread, which comes first.
+fn vote(mut results: Vec<SearchResult>) -> SearchResult {
+    let mate = results.iter().enumerate().filter(|(_, r)| r.score >= MATE_BOUND).max_by_key(|(_, r)| r.score);
+    if let Some((i, _)) = mate {
+        return results.swap_remove(i);
+    }
+    let worst = results.iter().map(|r| r.score).min().unwrap_or(0);
+    let votes = |best: ShortMove| -> i64 {
+        results.iter().filter(|r| r.best == best).map(|r| (r.score - worst + 20) as i64 * r.depth as i64).sum()
+    };
+    let mut chosen = 0;
+    for (i, candidate) in results.iter().enumerate().skip(1) {
+        let current = &results[chosen];
+        if votes(candidate.best) > votes(current.best) || (candidate.best == current.best && candidate.depth > current.depth) {
+            chosen = i;
+        }
+    }
+    results.swap_remove(chosen)
+}
+
+impl Searcher<'_> {
+    /// Iterative deepening from the start depth until a limit is reached or the main thread is done.
+    /// Every iteration searches the best `multi_pv` root moves one after another, each line leaving
+    /// out the best moves of the lines before it, and reports them in order.
+    fn deepen(&mut self, board: &Board, first: ShortMove, start_depth: usize, mut time_manager: Option<TimeManager>, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+        let multi_pv = self.limits.multi_pv.clamp(1, self.limits.root_moves(board).len().max(1));
+        let mut lines = vec![SearchResult {
+            best: first,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: vec![first],
+            multi_pv: 1,
+        }];
+        let max_depth = self.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        'deepening: for depth in start_depth.min(max_depth)..=max_depth {
+            let mut iteration: Vec<SearchResult> = Vec::with_capacity(multi_pv);
+            self.excluded.clear();
+            for k in 0..multi_pv {
+                let previous = lines.get(k);
+                self.prev_pv = previous.map_or(vec![], |line| line.pv.clone());
+                let Some(score) = self.iterate(board, depth, previous.map_or(0, |line| line.score)) else {
+                    lines[0].nodes = self.flush_nodes();
+                    lines[0].time = self.start.elapsed();
+                    break 'deepening;
+                };
+                let pv = self.pv[0][..self.pv_len[0]].to_vec();
+                let Some(best) = pv.first().copied() else { break };
+                self.excluded.push(best);
+                iteration.push(SearchResult { best, score, depth, nodes: 0, time: Duration::ZERO, pv, multi_pv: k + 1 });
+            }
+            if iteration.is_empty() {
+                break;
+            }
+            // A later line can come out ahead of an earlier one when the search is unstable.
+            iteration.sort_by_key(|line| -line.score);
+            let nodes = self.flush_nodes();
+            let time = self.start.elapsed();
+            for (k, line) in iteration.iter_mut().enumerate() {
+                line.multi_pv = k + 1;
+                line.nodes = nodes;
+                line.time = time;
+                on_iteration(line);
+            }
+            lines = iteration;
+            // A mate within the full width depth can not be improved on by searching deeper.
+            if lines.iter().all(|line| line.score.abs() >= MATE_BOUND && (MATE_SCORE - line.score.abs()) as usize <= depth) {
+                break;
+            }
+            if let Some(manager) = time_manager.as_mut() {
+                manager.update(self.shared.clock.elapsed(), lines[0].score, lines[0].best);
+                if manager.should_stop(self.shared.clock.elapsed()) {
+                    break;
+                }
+            }
+        }
+        lines.swap_remove(0)
+    }
+}
+
+#[test]
+fn finds_mate_and_material() {
+    let mut board = Board::new();
+    board.load_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1").expect("failed to load fen");
+    let result = search(&board, SearchLimits { depth: Some(4), ..Default::default() });
+    assert_eq!(result.best.to_string(), "d1d8");
+    assert_eq!(result.score, MATE_SCORE - 1);
+
+    board.load_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").expect("failed to load fen");
+    let result = search(&board, SearchLimits { nodes: Some(20_000), ..Default::default() });
+    assert_eq!(result.best.to_string(), "d2d5");
+    assert!(result.score > 0);
+}
+
+#[test]
+fn pvs_reports_lines_and_mates() {
+    let mut board = Board::new();
+    // Mate in 2: 1. Rxd8+ Rxd8 2. Rxd8#
+    board.load_fen("r2r2k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").expect("failed to load fen");
+    let mut depths = vec![];
+    let tt = TranspositionTable::new(1).expect("1 MB fits");
+    let result = search_with(&board, SearchLimits { depth: Some(5), ..Default::default() }, &tt, 1, |r| depths.push(r.depth));
+    assert_eq!(result.pv.iter().map(ShortMove::to_string).collect::<Vec<_>>(), ["d2d8", "a8d8", "d1d8"]);
+    assert_eq!(result.mate_in(), Some(2));
+    assert_eq!(depths, [1, 2, 3, 4], "should stop once the mate is proven");
+
+    board.load_fen("6k1/5ppp/8/8/8/8/r4PPP/r5K1 w - - 0 1").expect("failed to load fen");
+    let result = search(&board, SearchLimits { depth: Some(2), ..Default::default() });
+    assert_eq!(result.mate_in(), Some(0));
+}
+
+#[test]
+fn lazy_smp_shares_the_search() {
+    let mut board = Board::new();
+    board.load_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").expect("failed to load fen");
+    let tt = TranspositionTable::new(1).expect("1 MB fits");
+    let mut reported = vec![];
+    let result = search_with(&board, SearchLimits { depth: Some(5), ..Default::default() }, &tt, 3, |r| reported.push(r.nodes));
+    assert_eq!(result.best.to_string(), "d2d5");
+    assert!(result.nodes >= reported.last().copied().unwrap_or(0), "nodes should be summed over the threads");
+
+    let results = vec![
+        SearchResult { best: board.parse_uci_move("d2d5").expect("legal"), score: 800, depth: 4, nodes: 0, time: Duration::ZERO, pv: vec![], multi_pv: 1 },
+        SearchResult { best: board.parse_uci_move("d2d1").expect("legal"), score: 0, depth: 5, nodes: 0, time: Duration::ZERO, pv: vec![], multi_pv: 1 },
+        SearchResult { best: board.parse_uci_move("d2d5").expect("legal"), score: 790, depth: 5, nodes: 0, time: Duration::ZERO, pv: vec![], multi_pv: 1 },
+    ];
+    let voted = vote(results);
+    assert_eq!((voted.best.to_string(), voted.depth), ("d2d5".to_string(), 5), "the deepest result of the winning move");
+}
+
+#[test]
+fn multi_pv_searches_the_best_lines() {
+    let mut board = Board::new();
+    board.load_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").expect("failed to load fen");
+    let mut lines = vec![];
+    let result = search(&board, SearchLimits { depth: Some(3), multi_pv: 2, ..Default::default() });
+    assert_eq!((result.best.to_string(), result.multi_pv), ("d2d5".to_string(), 1));
+    search_with(&board, SearchLimits { depth: Some(3), multi_pv: 2, ..Default::default() }, &TranspositionTable::new(1).expect("1 MB fits"), 1, |r| lines.push(r.clone()));
+    let last: Vec<_> = lines.iter().filter(|r| r.depth == 3).collect();
+    assert_eq!(last.iter().map(|r| r.multi_pv).collect::<Vec<_>>(), [1, 2]);
+    assert!(last[0].score > last[1].score && last[0].best != last[1].best);
+
+    // More lines than legal moves.
+    board.load_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").expect("failed to load fen");
+    let mut lines = vec![];
+    search_with(&board, SearchLimits { depth: Some(2), multi_pv: 5, ..Default::default() }, &TranspositionTable::new(1).expect("1 MB fits"), 1, |r| lines.push((r.depth, r.multi_pv)));
+    assert_eq!(lines, [(1, 1), (1, 2), (1, 3), (2, 1), (2, 2), (2, 3)]);
+}
+
+#[test]
+fn search_moves_restrict_the_root() {
+    use super::{mcts, SearchTree};
+
+    let mut board = Board::new();
+    // Mate in 2: 1. Rxd8+ Rxd8 2. Rxd8#
+    board.load_fen("r2r2k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").expect("failed to load fen");
+    let search_moves = vec![board.parse_uci_move("g2g3").expect("legal"), board.parse_uci_move("h2h3").expect("legal")];
+    let limits = SearchLimits { depth: Some(4), search_moves: search_moves.clone(), ..Default::default() };
+    let result = search(&board, limits);
+    assert!(search_moves.contains(&result.best) && result.pv[0] == result.best);
+    assert!(result.score < MATE_BOUND);
+
+    // The tree already proved the mate, the restricted search still looks at its own moves.
+    let mut tree = SearchTree::new(board.clone(), 16);
+    mcts::search_with(&mut tree, SearchLimits { nodes: Some(200_000), ..Default::default() }, 1, |_| {});
+    let result = mcts::search_with(&mut tree, SearchLimits { nodes: Some(2_000), search_moves: search_moves.clone(), ..Default::default() }, 1, |_| {});
+    assert!(search_moves.contains(&result.best) && result.pv[0] == result.best);
+    assert!(result.score < MATE_BOUND && result.nodes >= 2_000);
+}
+
+#[test]
+fn captures_are_ordered_and_delta_pruned() {
+    let mut board = Board::new();
+    // The pawn and the queen can take the rook, the pawn and the rook the knight.
+    board.load_fen("4k3/8/8/2n1r3/3P4/8/2R4Q/6K1 w - - 0 1").expect("failed to load fen");
+    let eval = Evaluation::new(&board);
+    let ordered = order_moves(&board, &eval, generate_legal_moves(&board), None);
+    let captures: Vec<_> = ordered.iter().take_while(|m| m.is_capturing()).map(ShortMove::to_string).collect();
+    assert_eq!(captures, ["d4e5", "h2e5", "d4c5", "c2c5"]);
+
+    // A queen down, taking a pawn can not get back to alpha.
+    board.load_fen("3qk3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").expect("failed to load fen");
+    let tt = TranspositionTable::new(1).expect("1 MB fits");
+    let shared = SharedSearch { clock: SearchLimits::default().clock(), nodes: AtomicU64::new(0), done: AtomicBool::new(false) };
+    let mut searcher = Searcher::new(SearchLimits::default(), &tt, &shared, Instant::now());
+    let eval = Evaluation::new(&board);
+    let stand_pat = eval.evaluate(&board);
+    searcher.quiescence(&board, &eval, stand_pat + 400, stand_pat + 401);
+    assert_eq!(searcher.nodes, 1, "the capture should be pruned");
+    searcher.quiescence(&board, &eval, stand_pat, stand_pat + 1);
+    assert!(searcher.nodes > 2, "captures that can raise alpha are searched");
+}
@@ -0,0 +1,183 @@
+use std::{collections::HashMap, time::Instant};
+
+use chess_core::{board::Board, moves::{generate_legal_moves, ShortMove}};
+
+use super::{alphabeta::{SearchLimits, SearchResult, MATE_SCORE}, time::SearchClock};
+
+/// Nodes searched between checks of the limits.
+const CHECK_INTERVAL: u64 = 1024;
+/// Positions kept as disproven before the cache starts over.
+const CACHE_CAPACITY: usize = 1 << 22;
+
+/*
+* Full width search for a forced mate of the side to move, from mates in one up to the number of
+* moves asked for. The attacker needs one move that mates against every defence, so the tree is
+* an AND-OR tree and a branch is done as soon as it is decided:
+*
+* - the attacker's last move has to give check, so only checks are tried there,
+* - checks are tried first on every other move, they are the most likely to force mate,
+* - positions the attacker can't mate from within n moves are cached and not searched again for
+*   n or fewer moves.
+*/
+
+#[derive(Debug)]
+pub enum MateSearch {
+    /// The mating line against the longest defence.
+    Mate(SearchResult),
+    /// There is no forced mate within the number of moves.
+    NoMate,
+    /// The limits ran out before the search was done.
+    Aborted,
+}
+
+struct MateSearcher {
+    limits: SearchLimits,
+    clock: SearchClock,
+    nodes: u64,
+    aborted: bool,
+    /// Most moves the side to move is proven not to mate in.
+    disproven: HashMap<u64, usize>,
+}
+
+impl MateSearcher {
+    fn out_of_budget(&mut self) -> bool {
+        if !self.aborted && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.aborted = self.limits.nodes.is_some_and(|n| self.nodes >= n)
+                || self.limits.time.is_some_and(|t| self.clock.elapsed() >= t)
+                || self.limits.is_stopped();
+        }
+        self.aborted
+    }
+
+    /// A move mating within `n` moves. At the root only the moves the limits allow are tried.
+    fn attack(&mut self, board: &Board, n: usize, root: bool) -> Option<ShortMove> {
+        self.nodes += 1;
+        if self.out_of_budget() {
+            return None;
+        }
+        let key = board.hash();
+        if !root && self.disproven.get(&key).is_some_and(|moves| *moves >= n) {
+            return None;
+        }
+        let mut candidates = vec![];
+        for m in generate_legal_moves(board) {
+            if root && !self.limits.allows(m) {
+                continue;
+            }
+            let mut next = board.clone();
+            next.make_move(m);
+            let check = next.in_check();
+            if check || n > 1 {
+                candidates.push((!check, m, next));
+            }
+        }
+        // Stable, so checks keep the move generator's order.
+        candidates.sort_by_key(|(quiet, _, _)| *quiet);
+        for (_, m, next) in candidates {
+            if self.defend(&next, n) {
+                return Some(m);
+            }
+            if self.aborted {
+                return None;
+            }
+        }
+        if !root {
+            if self.disproven.len() >= CACHE_CAPACITY {
+                self.disproven.clear();
+            }
+            let moves = self.disproven.entry(key).or_default();
+            *moves = n.max(*moves);
+        }
+        None
+    }
+
+    /// Whether the attacker, who just moved, mates against every defence within `n` moves,
+    /// counting the move just played.
+    fn defend(&mut self, board: &Board, n: usize) -> bool {
+        self.nodes += 1;
+        let moves = generate_legal_moves(board);
+        if moves.is_empty() {
+            return board.in_check();
+        }
+        n > 1 && moves.iter().all(|m| {
+            let mut next = board.clone();
+            next.make_move(*m);
+            self.attack(&next, n - 1, false).is_some()
+        })
+    }
+
+    /// Mating line from a proven mate in `n`, the defender picks the replies that hold out longest.
+    fn line(&mut self, board: &Board, first: ShortMove, n: usize) -> Vec<ShortMove> {
+        let mut pv = vec![first];
+        let mut board = board.clone();
+        board.make_move(first);
+        let mut n = n;
+        while n > 1 {
+            let longest = generate_legal_moves(&board).into_iter().filter_map(|reply| {
+                let mut next = board.clone();
+                next.make_move(reply);
+                (1..n).find_map(|moves| self.attack(&next, moves, false).map(|m| (moves, reply, m)))
+            }).max_by_key(|(moves, _, _)| *moves);
+            let Some((moves, reply, m)) = longest else { break };
+            board.make_move(reply);
+            board.make_move(m);
+            pv.extend([reply, m]);
+            n = moves;
+        }
+        pv
+    }
+}
+
+/// Look for a forced mate of the side to move in up to `moves` moves, shortest first, and stop as
+/// soon as one is proven. Only the root moves the limits allow are tried.
+pub fn search(board: &Board, moves: usize, limits: SearchLimits) -> MateSearch {
+    let start = Instant::now();
+    let mut searcher = MateSearcher { clock: limits.clock(), limits, nodes: 0, aborted: false, disproven: HashMap::new() };
+    for n in 1..=moves {
+        if let Some(first) = searcher.attack(board, n, true) {
+            let pv = searcher.line(board, first, n);
+            return MateSearch::Mate(SearchResult {
+                best: first,
+                score: MATE_SCORE - (2 * n as i32 - 1),
+                depth: 2 * n - 1,
+                nodes: searcher.nodes,
+                time: start.elapsed(),
+                pv,
+                multi_pv: 1,
+            });
+        }
+        if searcher.aborted {
+            return MateSearch::Aborted;
+        }
+    }
+    MateSearch::NoMate
+}
+
+#[test]
+fn proves_mates_and_their_absence() {
+    let mut board = Board::new();
+    // Mate in 2: 1. Rxd8+ Rxd8 2. Rxd8#
+    board.load_fen("r2r2k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").expect("failed to load fen");
+    match search(&board, 5, SearchLimits::default()) {
+        MateSearch::Mate(result) => {
+            assert_eq!(result.mate_in(), Some(2));
+            assert_eq!(result.pv.iter().map(ShortMove::to_string).collect::<Vec<_>>(), ["d2d8", "a8d8", "d1d8"]);
+        },
+        other => panic!("expected a mate, got {other:?}"),
+    }
+    assert!(matches!(search(&board, 1, SearchLimits::default()), MateSearch::NoMate));
+
+    // Mate in 3 with a quiet first move: 1. Kb6 Kb8 2. Rc7 Ka8 3. Rc8#
+    board.load_fen("k7/2R5/K7/8/8/8/8/8 w - - 0 1").expect("failed to load fen");
+    match search(&board, 3, SearchLimits::default()) {
+        MateSearch::Mate(result) => {
+            assert!(result.mate_in().is_some_and(|n| n <= 3));
+            assert_eq!(result.pv.len() as i32, result.mate_in().expect("mate") * 2 - 1);
+        },
+        other => panic!("expected a mate, got {other:?}"),
+    }
+
+    let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    board.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").expect("failed to load fen");
+    assert!(matches!(search(&board, 4, SearchLimits { stop: Some(stop), ..Default::default() }), MateSearch::Aborted));
+}
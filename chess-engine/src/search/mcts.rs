@@ -0,0 +1,381 @@
+use std::{sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Mutex}, thread, time::Instant};
+
+use chess_core::{board::Board, moves::{generate_legal_moves, ShortMove}};
+
+use crate::eval::Evaluation;
+
+use super::{alphabeta::{SearchLimits, SearchResult, MATE_SCORE, MAX_DEPTH}, time::SearchClock, tree::ROOT, ucb_score, SearchBranch, SearchLeaf, SearchTree, DELTA, DRAW, LOSS, WIN};
+
+/*
+* Reference: https://www.chessprogramming.org/Monte-Carlo_Tree_Search
+* Reference: https://dke.maastrichtuniversity.nl/m.winands/documents/uctloa.pdf (MCTS-Solver)
+*/
+
+/// Centipawns at which a position is valued at about 0.76, see [value_of].
+const VALUE_SCALE: f32 = 400.0;
+/// Softmax temperature of the move priors in centipawns.
+const PRIOR_SCALE: f32 = 100.0;
+/// Iterations between checks of the budgets and of the principal variation.
+const CHECK_INTERVAL: u64 = 256;
+/// Loss added to every node on the path of an unfinished iteration so other threads select
+/// elsewhere, see [Searcher::iterate].
+const VIRTUAL_LOSS: f32 = 1.0;
+
+/// Map a centipawn score onto the (-1, 1) value range of the tree.
+fn value_of(cp: i32) -> f32 {
+    (cp as f32 / VALUE_SCALE).tanh()
+}
+
+fn centipawns(value: f32) -> i32 {
+    (value.clamp(-0.999, 0.999).atanh() * VALUE_SCALE) as i32
+}
+
+fn outcome_value(outcome: u16) -> f32 {
+    match outcome {
+        WIN => 1.0,
+        LOSS => -1.0,
+        _ => 0.0,
+    }
+}
+
+/// Children of the position with priors from a softmax over the static evaluation after each move.
+/// `eval` is the evaluation of the board, which is updated for each move.
+fn expand(board: &Board, eval: &Evaluation) -> Vec<SearchBranch> {
+    let moves = generate_legal_moves(board);
+    let scores: Vec<f32> = moves.iter().map(|m| {
+        let mut next = board.clone();
+        next.make_move(*m);
+        -eval.after(board, *m).evaluate(&next) as f32 / PRIOR_SCALE
+    }).collect();
+    let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let weights: Vec<f32> = scores.iter().map(|s| (s - max).exp()).collect();
+    let sum: f32 = weights.iter().sum();
+    moves.iter().zip(weights).map(|(m, w)| SearchBranch::new(*m, w / sum)).collect()
+}
+
+/// Outcome of a node for its mover once its children are proven, see MCTS-Solver.
+fn solve(children: &[SearchBranch]) -> u16 {
+    if children.iter().any(|c| c.outcome() == WIN) {
+        LOSS
+    } else if children.iter().all(|c| c.outcome() == LOSS) {
+        WIN
+    } else if children.iter().all(SearchBranch::is_final) {
+        DRAW
+    } else {
+        DELTA
+    }
+}
+
+/// Proven wins first, then the most visited, proven losses last.
+fn rank(child: &SearchBranch) -> (u8, u32) {
+    let outcome = match child.outcome() {
+        WIN => 2,
+        LOSS => 0,
+        _ => 1,
+    };
+    (outcome, child.visits)
+}
+
+fn best_child(tree: &SearchTree, index: usize) -> Option<usize> {
+    tree.children(index).max_by_key(|c| rank(tree.node(*c)))
+}
+
+/// The tree and the number of iterations between selection and backup, which is guarded by the
+/// same lock.
+struct SharedTree<'a> {
+    tree: &'a mut SearchTree,
+    in_flight: usize,
+}
+
+struct Searcher {
+    limits: SearchLimits,
+    start: Instant,
+    clock: SearchClock,
+    iterations: AtomicU64,
+    /// Set when the main thread is done so the helpers stop too.
+    done: AtomicBool,
+    /// Evaluation of the root, selection updates a copy along the path.
+    root_eval: Evaluation,
+}
+
+impl Searcher {
+    /// Select down to a leaf, expand and evaluate it, and back the value up. The tree is only locked
+    /// to select and to back up, the leaf is expanded and evaluated in between while the path
+    /// carries a virtual loss. Returns whether the root is proven.
+    fn iterate(&self, shared: &Mutex<SharedTree>) -> bool {
+        let mut guard = shared.lock().expect("search tree lock poisoned");
+        if guard.tree.is_full() {
+            // Recycling moves nodes, so wait for the paths of other threads to be backed up.
+            if guard.in_flight > 0 {
+                drop(guard);
+                thread::yield_now();
+                return false;
+            }
+            guard.tree.recycle();
+        }
+        let tree = &mut *guard.tree;
+        let mut board = tree.root.clone();
+        let mut eval = self.root_eval;
+        let mut path = vec![ROOT];
+        let restricted = !self.limits.search_moves.is_empty();
+        let leaf = loop {
+            let index = *path.last().expect("the path starts at the root");
+            let node = *tree.node(index);
+            let virtual_node = tree.node_mut(index);
+            virtual_node.visits += 1;
+            virtual_node.score -= VIRTUAL_LOSS;
+            // A root proven through a move left out of `searchmoves` still has its moves to search.
+            let is_final = node.is_final() && !(index == ROOT && restricted);
+            if is_final || !tree.is_expanded(index) || path.len() > MAX_DEPTH {
+                break node;
+            }
+            let parent_visits = node.visits.max(1);
+            let child = tree.children(index)
+                .filter(|c| index != ROOT || self.limits.allows(tree.node(*c).short))
+                .max_by(|a, b| ucb_score(parent_visits, tree.node(*a)).total_cmp(&ucb_score(parent_visits, tree.node(*b))))
+                .expect("expanded nodes have at least one searched child");
+            eval.make_move(&board, tree.node(child).short);
+            board.make_move(tree.node(child).short);
+            path.push(child);
+        };
+        let grow = !leaf.is_final() && !tree.is_expanded(*path.last().expect("the path starts at the root"));
+        guard.in_flight += 1;
+        drop(guard);
+
+        let expansion = grow.then(|| (expand(&board, &eval), eval.evaluate(&board)));
+
+        let mut guard = shared.lock().expect("search tree lock poisoned");
+        guard.in_flight -= 1;
+        let tree = &mut *guard.tree;
+        let index = *path.last().expect("the path starts at the root");
+        let mut value = match expansion {
+            None if leaf.is_final() => outcome_value(leaf.outcome()),
+            None => leaf.mean(),
+            Some((children, _)) if children.is_empty() => {
+                // Checkmate is a win for the side that just moved.
+                let outcome = if board.in_check() { WIN } else { DRAW };
+                tree.node_mut(index).flags = outcome;
+                outcome_value(outcome)
+            },
+            Some(_) if board.halfmove_clock() >= 100 => {
+                tree.node_mut(index).flags = DRAW;
+                0.0
+            },
+            Some((children, evaluation)) => {
+                // Another thread may have grown the leaf meanwhile. When the tree is full the leaf
+                // is still evaluated, just not grown.
+                if !tree.is_expanded(index) {
+                    tree.expand(index, children);
+                }
+                -value_of(evaluation)
+            },
+        };
+        tree.node_mut(index).score += VIRTUAL_LOSS + value;
+        for index in path.iter().rev().skip(1) {
+            let outcome = solve(&tree.children(*index).map(|c| *tree.node(c)).collect::<Vec<_>>());
+            value = if outcome != DELTA { outcome_value(outcome) } else { -value };
+            let node = tree.node_mut(*index);
+            node.flags = outcome;
+            node.score += VIRTUAL_LOSS + value;
+        }
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+        self.is_root_solved(tree)
+    }
+
+    /// Whether the result of the root is proven, counting only the moves that may be searched.
+    fn is_root_solved(&self, tree: &SearchTree) -> bool {
+        if self.limits.search_moves.is_empty() {
+            return tree.node(ROOT).is_final();
+        }
+        let children: Vec<SearchBranch> = tree.children(ROOT).map(|c| *tree.node(c)).filter(|c| self.limits.allows(c.short)).collect();
+        tree.is_expanded(ROOT) && solve(&children) != DELTA
+    }
+
+    fn iterations(&self) -> u64 {
+        self.iterations.load(Ordering::Relaxed)
+    }
+
+    /// Whether the helper threads should stop.
+    fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+            || self.limits.is_stopped()
+            || self.limits.nodes.is_some_and(|n| self.iterations() >= n)
+    }
+
+    fn out_of_budget(&self, pv: &SearchLeaf) -> bool {
+        self.limits.nodes.is_some_and(|n| self.iterations() >= n)
+            || self.limits.time.is_some_and(|t| self.clock.elapsed() >= t)
+            || pv.depth as usize >= self.limits.depth.unwrap_or(MAX_DEPTH)
+            || self.limits.is_stopped()
+    }
+}
+
+/// Follow the best children from the root.
+fn principal_variation(tree: &SearchTree, limits: &SearchLimits) -> SearchLeaf {
+    principal_variations(tree, 1, limits).swap_remove(0)
+}
+
+/// Lines of the best root moves the limits allow, best first, following the best children below
+/// each. An empty line stands in when the root has no such children.
+fn principal_variations(tree: &SearchTree, count: usize, limits: &SearchLimits) -> Vec<SearchLeaf> {
+    let mut children: Vec<usize> = tree.children(ROOT).filter(|c| limits.allows(tree.node(*c).short)).collect();
+    // Ties go to the last child, like in [best_child].
+    children.sort_by_key(|c| std::cmp::Reverse((rank(tree.node(*c)), *c)));
+    if children.is_empty() {
+        return vec![SearchLeaf { variation: vec![], depth: 0, flags: DELTA, score: 0.0 }];
+    }
+    children.into_iter().take(count).map(|child| {
+        let mut variation = vec![tree.node(child).short];
+        let mut index = child;
+        while let Some(best) = best_child(tree, index) {
+            variation.push(tree.node(best).short);
+            index = best;
+        }
+        SearchLeaf {
+            depth: variation.len() as u16,
+            variation,
+            flags: tree.node(child).outcome(),
+            score: tree.node(child).mean(),
+        }
+    }).collect()
+}
+
+fn search_result(pv: &SearchLeaf, searcher: &Searcher) -> SearchResult {
+    let plies = pv.variation.len() as i32;
+    let score = match pv.flags {
+        WIN => MATE_SCORE - plies,
+        LOSS => -MATE_SCORE + plies,
+        DRAW => 0,
+        _ => centipawns(pv.score),
+    };
+    SearchResult {
+        best: pv.variation.first().copied().unwrap_or(ShortMove::INVALID),
+        score,
+        depth: pv.depth as usize,
+        nodes: searcher.iterations(),
+        time: searcher.start.elapsed(),
+        pv: pv.variation.clone(),
+        multi_pv: 1,
+    }
+}
+
+/// Find the best move with Monte Carlo tree search using PUCT selection and static evaluation at
+/// the leaves. Proven wins, losses and draws are backed up exactly. The search ends when the root is
+/// proven, a limit is reached or the principal variation reaches the depth limit. The tree is kept
+/// for the next search, recycling its least visited nodes whenever it fills up.
+///
+/// With more than one thread the threads grow the same tree, kept apart by virtual loss. With
+/// `multi_pv` set the lines of that many of the best root moves are reported.
+pub(crate) fn search_with(tree: &mut SearchTree, limits: SearchLimits, threads: usize, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+    let board = tree.root.clone();
+    let searcher = Searcher {
+        clock: limits.clock(),
+        limits,
+        start: Instant::now(),
+        iterations: AtomicU64::new(0),
+        done: AtomicBool::new(false),
+        root_eval: Evaluation::new(&tree.root),
+    };
+    if generate_legal_moves(&board).is_empty() {
+        let mut result = search_result(&principal_variation(tree, &searcher.limits), &searcher);
+        result.score = if board.in_check() { -MATE_SCORE } else { 0 };
+        return result;
+    }
+    let mut time_manager = searcher.limits.time_manager();
+    let multi_pv = searcher.limits.multi_pv.max(1);
+    let report = |tree: &SearchTree, on_iteration: &mut dyn FnMut(&SearchResult)| {
+        let lines = principal_variations(tree, multi_pv, &searcher.limits);
+        for (k, line) in lines.iter().enumerate() {
+            on_iteration(&SearchResult { multi_pv: k + 1, ..search_result(line, &searcher) });
+        }
+        lines
+    };
+    let shared = Mutex::new(SharedTree { tree, in_flight: 0 });
+    let lines = thread::scope(|scope| {
+        for _ in 1..threads.max(1) {
+            scope.spawn(|| {
+                while !searcher.is_done() {
+                    searcher.iterate(&shared);
+                }
+            });
+        }
+        let mut reported_depth = 0;
+        let mut reported_nodes = 0;
+        let mut iterations = 0u64;
+        loop {
+            let root_final = searcher.iterate(&shared);
+            iterations += 1;
+            if !iterations.is_multiple_of(CHECK_INTERVAL) && !root_final && !searcher.limits.is_stopped() {
+                continue;
+            }
+            let pv = principal_variation(shared.lock().expect("search tree lock poisoned").tree, &searcher.limits);
+            if pv.depth > reported_depth {
+                reported_depth = pv.depth;
+                reported_nodes = searcher.iterations();
+                report(shared.lock().expect("search tree lock poisoned").tree, &mut on_iteration);
+            }
+            // Every check counts as an iteration for the time manager.
+            let out_of_time = time_manager.as_mut().is_some_and(|manager| {
+                let elapsed = searcher.clock.elapsed();
+                manager.update(elapsed, search_result(&pv, &searcher).score, pv.variation.first().copied().unwrap_or(ShortMove::INVALID));
+                manager.should_stop(elapsed)
+            });
+            if root_final || out_of_time || searcher.out_of_budget(&pv) {
+                break;
+            }
+        }
+        searcher.done.store(true, Ordering::Relaxed);
+        let tree = shared.lock().expect("search tree lock poisoned");
+        if searcher.iterations() != reported_nodes {
+            // The helpers may have added to the tree since.
+            return report(tree.tree, &mut on_iteration);
+        }
+        principal_variations(tree.tree, multi_pv, &searcher.limits)
+    });
+    let result = search_result(&lines[0], &searcher);
+    shared.into_inner().expect("search tree lock poisoned").tree.pvs = lines;
+    result
+}
+
+#[test]
+fn mcts_proves_mates() {
+    let mut board = Board::new();
+    // Mate in 2: 1. Rxd8+ Rxd8 2. Rxd8#
+    board.load_fen("r2r2k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").expect("failed to load fen");
+    let mut tree = SearchTree::new(board.clone(), 16);
+    let result = search_with(&mut tree, SearchLimits { nodes: Some(200_000), ..Default::default() }, 1, |_| {});
+    assert_eq!(result.pv.iter().map(ShortMove::to_string).collect::<Vec<_>>(), ["d2d8", "a8d8", "d1d8"]);
+    assert_eq!(result.score, MATE_SCORE - 3, "the mate should be proven");
+
+    board.load_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").expect("failed to load fen");
+    tree.reset(board.clone());
+    let result = search_with(&mut tree, SearchLimits { nodes: Some(5_000), ..Default::default() }, 1, |_| {});
+    assert_eq!(result.best.to_string(), "d2d5");
+    assert!(result.score > 0);
+
+    // Tree parallel search proves the same mate.
+    board.load_fen("r2r2k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1").expect("failed to load fen");
+    tree.reset(board);
+    let result = search_with(&mut tree, SearchLimits { nodes: Some(200_000), ..Default::default() }, 4, |_| {});
+    assert_eq!(result.score, MATE_SCORE - 3);
+    assert_eq!(tree.node(ROOT).visits as u64, result.nodes, "virtual losses should all be undone");
+}
+
+#[test]
+fn tree_recycles_and_reuses_subtrees() {
+    let mut tree = SearchTree::new(Board::default(), 1);
+    tree.set_capacity(2_000);
+    let result = search_with(&mut tree, SearchLimits { nodes: Some(3_000), ..Default::default() }, 1, |_| {});
+    assert!(tree.len() <= 2_000, "the tree should stay within its budget");
+    assert!(tree.node(ROOT).visits as u64 >= result.nodes, "recycling should keep the root statistics");
+
+    let child = best_child(&tree, ROOT).expect("the root is expanded");
+    let visits = tree.node(child).visits;
+    tree.advance(&result.pv[..1]);
+    assert_eq!(tree.node(ROOT).visits, visits, "the subtree under the played move should be reused");
+    assert!(tree.is_expanded(ROOT));
+
+    let unexplored = tree.children(ROOT).find(|c| !tree.is_expanded(*c)).expect("some replies are unexplored");
+    tree.advance(&[tree.node(unexplored).short]);
+    assert_eq!(tree.len(), 1, "an unexplored line starts a new tree");
+}
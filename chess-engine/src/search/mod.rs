@@ -1,45 +1,81 @@
-use chess_core::{board::{piece::{NUM_PIECE_KINDS, NUM_PIECE_SIDES}, Board}, moves::{MoveList, ShortMove}};
+use std::{sync::{atomic::{AtomicBool, Ordering}, Arc}, thread::JoinHandle};
 
-pub enum Searching {
-    Infinite,
-    Mate(usize),
-    Depth(usize),
-    Nodes(usize),
+use chess_core::moves::ShortMove;
+
+pub mod alphabeta;
+pub mod mate;
+pub mod mcts;
+pub mod time;
+pub mod tt;
+mod tree;
+
+pub(crate) use tree::{SearchBranch, SearchTree};
+
+/// Search algorithm used for `go`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    AlphaBeta,
+    Mcts,
 }
 
-impl Searching {
-    pub fn stop(&self) -> (ShortMove, Option<ShortMove>) {
-        todo!()
+impl Backend {
+    pub const NAMES: [&str; 2] = ["AlphaBeta", "MCTS"];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "AlphaBeta" => Some(Backend::AlphaBeta),
+            "MCTS" => Some(Backend::Mcts),
+            _ => None,
+        }
     }
 }
 
-pub(crate) struct SearchTree {
-    root: Board,
-    pvs: Vec<SearchLeaf<'static>>,
-    current_depth: u16,
+/// Handle to a search running on a worker thread.
+pub struct Searching {
+    stop: Arc<AtomicBool>,
+    /// Set while the search ponders, see [SearchLimits::ponder](alphabeta::SearchLimits::ponder).
+    ponder: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
 }
 
-impl SearchTree {
-    pub fn new(board: Board) -> Self {
-        Self {
-            root: board,
-            pvs: vec![],
-            current_depth: 0,
-        }
+impl Searching {
+    pub fn new(stop: Arc<AtomicBool>, ponder: Arc<AtomicBool>, handle: JoinHandle<()>) -> Self {
+        Self { stop, ponder, handle }
     }
-}
 
-struct SearchBranch<'a> {
-    short: ShortMove,
-    flags: u16,
-    score: i32,
-    children: Option<&'a[SearchBranch<'a>]>
+    /// The expected move was played, the ponder search goes on as a timed search.
+    pub fn ponderhit(&self) {
+        self.ponder.store(false, Ordering::Relaxed);
+        // Wake a ponder search that finished early and waits to report its best move.
+        self.handle.thread().unpark();
+    }
+
+    #[cfg(test)]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Signal the search to stop and wait for it to report its best move.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake a finished infinite search waiting for the signal.
+        self.handle.thread().unpark();
+        self.handle.join().expect("search thread panicked");
+    }
 }
 
-struct SearchLeaf<'a> {
-    variation: &'a [ShortMove],
+/// A line through the tree, such as the principal variation.
+pub(crate) struct SearchLeaf {
+    variation: Vec<ShortMove>,
     depth: u16,
+    /// Outcome of the line for the side to move at its root if it was proven.
     flags: u16,
+    /// Mean value of the first move for the side to move.
     score: f32,
 }
 
@@ -48,44 +84,14 @@ const DELTA: u16 = 0b0000_0000_0000_0000;
 const WIN: u16 = 0b0000_0000_0000_0001;
 const LOSS: u16 = 0b0000_0000_0000_0010;
 const DRAW: u16 = 0b0000_0000_0000_0011;
-const CHECK_EXTENSION: u16 = 0b0000_0000_0000_0100;
-const PROMOTION_REEVAL: u16 = 0b0000_0000_0000_1000;
-
-fn eval_moves(moves: MoveList, board: &Board) -> impl Iterator<Item = (i16, ShortMove)> {
-    let mut board = board.clone();
-    moves.into_iter().map(move |m| {
-        if m.is_capturing() {
-            let (side, piece) = board.square(m.dest()).expect("can not capture empty space");
-            board.remove_piece(side, piece, m.dest());
-        }
-        // TODO: add promote, enpasant, castling, dont discard work to generate post move board
-        let (side, piece) = board.square(m.src()).expect("can not move from empty space");
-        board.move_piece(side, piece, m.src(), m.dest());
-        let mut sum = 0i16;
-        for side in 0..NUM_PIECE_SIDES {
-            let mut score = 0i16;
-            for piece in 0..NUM_PIECE_KINDS {
-                let count = board.piece(side.into(), piece.into()).count_ones();
-                score = count as i16 * MATERIAL_VALUES[piece as usize];
-            };
-            if side == 1 {
-                sum -= score;
-            } else {
-                sum += score;
-            }
-        }
-        (sum, m)
-    })
-}
-
-fn sort_moves(input: impl Iterator<Item = (i16, ShortMove)>) -> Box<[(i16, ShortMove)]> {
-    let mut x = input.collect::<Vec<_>>();
-    x.sort_unstable_by(|a, b| b.0.cmp(&a.0));
-    x.into_boxed_slice()
-}
-
-const MATERIAL_VALUES: [i16; NUM_PIECE_KINDS] = [ 10, 30, 40, 50, 90, 0 ];
+/// PUCT exploration constant, higher favours moves with few visits and a high prior.
+const EXPLORATION: f32 = 1.5;
 
-fn ucb_score(depth_dist: u16, eval_score: f32) -> f32 {
-    todo!()
+/// PUCT score of a child from the parent's perspective. Proven losses are never selected.
+fn ucb_score(parent_visits: u32, child: &SearchBranch) -> f32 {
+    if child.outcome() == LOSS {
+        return f32::NEG_INFINITY;
+    }
+    let exploration = EXPLORATION * child.prior * (parent_visits as f32).sqrt() / (1 + child.visits) as f32;
+    child.mean() + exploration
 }
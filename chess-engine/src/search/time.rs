@@ -0,0 +1,178 @@
+use std::{sync::{atomic::{AtomicBool, Ordering}, Arc, OnceLock}, time::{Duration, Instant}};
+
+use chess_core::moves::ShortMove;
+
+/*
+* Reference: https://www.chessprogramming.org/Time_Management
+*/
+
+/// Moves assumed left until the next time control when the GUI doesn't send `movestogo`.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// The hard limit is at most this many times the soft limit.
+const HARD_LIMIT_FACTOR: u32 = 4;
+/// Share of the remaining clock a single move may use at most, in percent.
+const MAX_CLOCK_SHARE: u32 = 75;
+/// Never plan for less than this, the engine must always return a move.
+const MIN_TIME: Duration = Duration::from_millis(1);
+/// Drop in centipawns between iterations that counts as trouble and earns more time.
+const SCORE_DROP: i32 = 30;
+/// Iterations the best move must survive before the search saves time on it.
+const STABLE_ITERATIONS: u32 = 4;
+/// Expected ratio between the time of the next iteration and the last one.
+const BRANCHING_FACTOR: u32 = 2;
+
+/// Clock state of the side to move, from the `go` parameters.
+#[derive(Debug, Clone, Default)]
+pub struct TimeControl {
+    pub time: Option<Duration>,
+    pub increment: Duration,
+    pub moves_to_go: Option<u32>,
+    pub move_time: Option<Duration>,
+}
+
+/// The search aims to finish within `soft` and is cut off at `hard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLimits {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+impl TimeControl {
+    /// Limits for this move after subtracting the network and GUI overhead, or `None` if the
+    /// search is not timed.
+    pub fn limits(&self, overhead: Duration) -> Option<TimeLimits> {
+        if let Some(move_time) = self.move_time {
+            let time = move_time.saturating_sub(overhead).max(MIN_TIME);
+            return Some(TimeLimits { soft: time, hard: time });
+        }
+        let available = self.time?.saturating_sub(overhead);
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, DEFAULT_MOVES_TO_GO);
+        let hard = (available * MAX_CLOCK_SHARE / 100).max(MIN_TIME);
+        let soft = available / moves_to_go + self.increment * 3 / 4;
+        Some(TimeLimits {
+            soft: soft.clamp(MIN_TIME, hard),
+            hard: (soft * HARD_LIMIT_FACTOR).clamp(MIN_TIME, hard),
+        })
+    }
+}
+
+/// Time counted against the limits of a search. It stands still while the search ponders and
+/// starts when the ponder flag is cleared by `ponderhit`.
+pub struct SearchClock {
+    ponder: Option<Arc<AtomicBool>>,
+    start: OnceLock<Instant>,
+}
+
+impl SearchClock {
+    pub fn new(ponder: Option<Arc<AtomicBool>>) -> Self {
+        let clock = Self { ponder, start: OnceLock::new() };
+        if !clock.is_pondering() {
+            clock.start.get_or_init(Instant::now);
+        }
+        clock
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.as_ref().is_some_and(|ponder| ponder.load(Ordering::Relaxed))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        if self.is_pondering() {
+            return Duration::ZERO;
+        }
+        self.start.get_or_init(Instant::now).elapsed()
+    }
+}
+
+/// Decides after every iteration whether to start another one. The soft limit is stretched when
+/// the score drops or the best move changes, and shrunk when the best move is stable.
+pub struct TimeManager {
+    limits: TimeLimits,
+    /// Scale of the soft limit for the next iteration.
+    factor: f64,
+    previous: Option<(i32, ShortMove)>,
+    stable_iterations: u32,
+    elapsed: Duration,
+    last_iteration: Duration,
+}
+
+impl TimeManager {
+    pub fn new(limits: TimeLimits) -> Self {
+        Self {
+            limits,
+            factor: 1.0,
+            previous: None,
+            stable_iterations: 0,
+            elapsed: Duration::ZERO,
+            last_iteration: Duration::ZERO,
+        }
+    }
+
+    /// Record a finished iteration with its score from the side to move's perspective.
+    pub fn update(&mut self, elapsed: Duration, score: i32, best: ShortMove) {
+        self.factor = 1.0;
+        if let Some((previous_score, previous_best)) = self.previous {
+            if score < previous_score - SCORE_DROP {
+                self.factor *= 1.5;
+            }
+            if best == previous_best {
+                self.stable_iterations += 1;
+            } else {
+                self.stable_iterations = 0;
+                self.factor *= 1.4;
+            }
+            if self.stable_iterations >= STABLE_ITERATIONS {
+                self.factor *= 0.8;
+            }
+        }
+        self.previous = Some((score, best));
+        self.last_iteration = elapsed.saturating_sub(self.elapsed);
+        self.elapsed = elapsed;
+    }
+
+    /// Whether the search should stop instead of starting another iteration.
+    pub fn should_stop(&self, elapsed: Duration) -> bool {
+        let soft = self.limits.soft.mul_f64(self.factor).min(self.limits.hard);
+        // The next iteration would be cut off at the hard limit and wasted.
+        elapsed >= soft || elapsed + self.last_iteration * BRANCHING_FACTOR > self.limits.hard
+    }
+}
+
+#[test]
+fn time_limits_and_extensions() {
+    let ms = Duration::from_millis;
+    let control = TimeControl { time: Some(ms(60_000)), increment: ms(1_000), ..Default::default() };
+    let limits = control.limits(ms(100)).expect("the search is timed");
+    assert_eq!(limits.soft, ms(59_900) / 30 + ms(750));
+    assert_eq!(limits.hard, limits.soft * 4);
+
+    let last_move = TimeControl { time: Some(ms(1_000)), moves_to_go: Some(1), ..Default::default() };
+    assert_eq!(last_move.limits(ms(100)), Some(TimeLimits { soft: ms(675), hard: ms(675) }));
+    let move_time = TimeControl { move_time: Some(ms(500)), ..Default::default() };
+    assert_eq!(move_time.limits(ms(100)), Some(TimeLimits { soft: ms(400), hard: ms(400) }));
+    assert_eq!(TimeControl::default().limits(ms(100)), None);
+
+    let board = chess_core::board::Board::default();
+    let e2e4 = board.parse_uci_move("e2e4").expect("e2e4 is legal");
+    let d2d4 = board.parse_uci_move("d2d4").expect("d2d4 is legal");
+    let mut manager = TimeManager::new(TimeLimits { soft: ms(100), hard: ms(1_000) });
+    manager.update(ms(10), 20, e2e4);
+    assert!(!manager.should_stop(ms(90)));
+    assert!(manager.should_stop(ms(100)));
+    // A score drop with a new best move extends the soft limit.
+    manager.update(ms(20), -50, d2d4);
+    assert!(!manager.should_stop(ms(150)));
+    assert!(manager.should_stop(ms(220)));
+    // An iteration that would overrun the hard limit is not started.
+    let mut manager = TimeManager::new(TimeLimits { soft: ms(900), hard: ms(1_000) });
+    manager.update(ms(400), 0, e2e4);
+    assert!(manager.should_stop(ms(500)));
+
+    // The clock of a ponder search starts on ponderhit.
+    let ponder = Arc::new(AtomicBool::new(true));
+    let clock = SearchClock::new(Some(ponder.clone()));
+    std::thread::sleep(ms(20));
+    assert_eq!(clock.elapsed(), Duration::ZERO);
+    ponder.store(false, Ordering::Relaxed);
+    assert!(clock.elapsed() < ms(20));
+}
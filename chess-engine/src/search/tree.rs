@@ -0,0 +1,204 @@
+use std::{collections::VecDeque, ops::Range};
+
+use chess_core::{board::Board, moves::{ShortMove, MAX_LEGAL_MOVES}};
+
+use super::{SearchLeaf, DELTA, FINAL_MASK};
+
+/// Index of the root in the arena.
+pub(crate) const ROOT: usize = 0;
+const BYTES_PER_MB: usize = 1024 * 1024;
+
+/// MCTS tree stored as an arena of nodes, the children of a node are contiguous. The arena is
+/// bounded by a memory budget, see [SearchTree::recycle].
+pub(crate) struct SearchTree {
+    pub(crate) root: Board,
+    nodes: Vec<SearchBranch>,
+    /// Maximum number of nodes.
+    capacity: usize,
+    pub(crate) pvs: Vec<SearchLeaf>,
+    pub(crate) current_depth: u16,
+}
+
+impl SearchTree {
+    /// Empty tree within a budget in MB, as given by the `Hash` option.
+    pub fn new(board: Board, hash_capacity: usize) -> Self {
+        let mut tree = Self {
+            root: board,
+            nodes: vec![],
+            capacity: 0,
+            pvs: vec![],
+            current_depth: 0,
+        };
+        tree.set_hash_capacity(hash_capacity);
+        tree.clear();
+        tree
+    }
+
+    /// Change the budget in MB, recycling nodes if the tree no longer fits.
+    pub fn set_hash_capacity(&mut self, hash_capacity: usize) {
+        self.set_capacity(hash_capacity.saturating_mul(BYTES_PER_MB) / size_of::<SearchBranch>());
+    }
+
+    /// Change the budget in nodes. It is always large enough to expand the root.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1 + MAX_LEGAL_MOVES);
+        if self.nodes.len() > self.capacity {
+            self.recycle();
+        }
+        // Give back memory of a larger budget.
+        self.nodes.shrink_to(self.capacity);
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.nodes.push(SearchBranch::new(ShortMove::INVALID, 1.0));
+    }
+
+    /// Start over from a new position, keeping the allocation.
+    pub fn reset(&mut self, board: Board) {
+        self.root = board;
+        self.clear();
+        self.pvs.clear();
+        self.current_depth = 0;
+    }
+
+    /// Move the root forward by moves played since the search started. The subtree under the moves
+    /// is kept if it was expanded, everything else is dropped.
+    pub fn advance(&mut self, moves: &[ShortMove]) {
+        let mut index = Some(ROOT);
+        for m in moves {
+            self.root.make_move(*m);
+            index = index.and_then(|i| self.children(i).find(|c| self.nodes[*c].short == *m));
+        }
+        match index {
+            Some(index) => self.rebuild(index, 0),
+            None => self.clear(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn node(&self, index: usize) -> &SearchBranch {
+        &self.nodes[index]
+    }
+
+    pub(crate) fn node_mut(&mut self, index: usize) -> &mut SearchBranch {
+        &mut self.nodes[index]
+    }
+
+    /// Indices of the children, empty if the node is not expanded.
+    pub(crate) fn children(&self, index: usize) -> Range<usize> {
+        let node = &self.nodes[index];
+        node.first_child as usize..node.first_child as usize + node.num_children as usize
+    }
+
+    pub(crate) fn is_expanded(&self, index: usize) -> bool {
+        self.nodes[index].num_children != 0
+    }
+
+    /// Whether a node with the most possible moves can still be expanded.
+    pub(crate) fn is_full(&self) -> bool {
+        self.nodes.len() + MAX_LEGAL_MOVES > self.capacity
+    }
+
+    /// Attach the children to the node. Returns false, leaving the node as it was, if they don't fit.
+    pub(crate) fn expand(&mut self, index: usize, children: Vec<SearchBranch>) -> bool {
+        if children.is_empty() || self.nodes.len() + children.len() > self.capacity {
+            return false;
+        }
+        self.nodes[index].first_child = self.nodes.len() as u32;
+        self.nodes[index].num_children = children.len() as u16;
+        self.nodes.extend(children);
+        true
+    }
+
+    /// Free about half of the budget by dropping the children of the least visited nodes. Their
+    /// statistics and proofs are kept, so they are expanded again if the search returns to them.
+    pub(crate) fn recycle(&mut self) {
+        let mut expanded: Vec<(u32, usize)> = self.nodes.iter()
+            .filter(|n| n.num_children != 0)
+            .map(|n| (n.visits, n.num_children as usize))
+            .collect();
+        expanded.sort_unstable_by_key(|(visits, _)| std::cmp::Reverse(*visits));
+        let mut kept = 1;
+        let mut threshold = 0;
+        for (visits, num_children) in expanded {
+            if kept + num_children > self.capacity / 2 {
+                // Children of nodes this visited or less are dropped, ties included.
+                threshold = visits + 1;
+                break;
+            }
+            kept += num_children;
+        }
+        self.rebuild(ROOT, threshold);
+    }
+
+    /// Copy the subtree under the node into a fresh arena with the node as the root, keeping only
+    /// the children of nodes visited at least `threshold` times.
+    fn rebuild(&mut self, index: usize, threshold: u32) {
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        let mut root = self.nodes[index];
+        root.num_children = 0;
+        nodes.push(root);
+        let mut queue = VecDeque::from([(ROOT, index)]);
+        while let Some((new, old)) = queue.pop_front() {
+            if self.nodes[old].visits < threshold {
+                continue;
+            }
+            let first_child = nodes.len();
+            for child in self.children(old) {
+                let mut node = self.nodes[child];
+                node.num_children = 0;
+                nodes.push(node);
+                queue.push_back((nodes.len() - 1, child));
+            }
+            nodes[new].first_child = first_child as u32;
+            nodes[new].num_children = self.nodes[old].num_children;
+        }
+        self.nodes = nodes;
+    }
+}
+
+/// Node of the MCTS tree, reached by playing `short`. Scores and flags are from the perspective of
+/// the side that played the move.
+#[derive(Clone, Copy)]
+pub(crate) struct SearchBranch {
+    pub(crate) short: ShortMove,
+    pub(crate) flags: u16,
+    pub(crate) visits: u32,
+    /// Sum of the backed up values, each in [-1, 1].
+    pub(crate) score: f32,
+    /// Policy probability of the move among its siblings.
+    pub(crate) prior: f32,
+    /// Index of the first child in the arena.
+    first_child: u32,
+    /// 0 until the node is expanded.
+    num_children: u16,
+}
+
+impl SearchBranch {
+    pub(crate) fn new(short: ShortMove, prior: f32) -> Self {
+        Self { short, flags: DELTA, visits: 0, score: 0.0, prior, first_child: 0, num_children: 0 }
+    }
+
+    /// Mean value, or 0 if never visited.
+    pub(crate) fn mean(&self) -> f32 {
+        if self.visits == 0 { 0.0 } else { self.score / self.visits as f32 }
+    }
+
+    pub(crate) fn is_final(&self) -> bool {
+        self.flags & FINAL_MASK != DELTA
+    }
+
+    pub(crate) fn outcome(&self) -> u16 {
+        self.flags & FINAL_MASK
+    }
+}
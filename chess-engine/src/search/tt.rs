@@ -0,0 +1,243 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use anyhow::anyhow;
+
+use chess_core::moves::ShortMove;
+
+use super::alphabeta::MATE_BOUND;
+
+/*
+* Reference: https://www.chessprogramming.org/Transposition_Table
+*
+* Each entry is packed into a single atomic word so threads can share the table without locks,
+* a torn entry can't happen and a racing overwrite only loses an entry:
+*
+* bits 0-15   move
+* bits 16-31  score
+* bits 32-39  depth
+* bits 40-41  bound, never 0 for a used entry
+* bits 42-47  age
+* bits 48-63  lower bits of the key, to verify the entry belongs to the position. The bucket is
+*             picked by the upper bits, so these must not overlap them.
+*/
+
+const ENTRIES_PER_BUCKET: usize = 4;
+const BYTES_PER_MB: usize = 1024 * 1024;
+const AGE_MASK: u8 = 0b11_1111;
+/// Buckets sampled for `hashfull`.
+const HASHFULL_SAMPLE: usize = 1000;
+/// Depth one entry of an older search is worth when choosing which entry to replace.
+const AGE_WEIGHT: i32 = 8;
+
+/// How the stored score relates to the real one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact = 1,
+    /// The real score is at least the stored one, the node failed high.
+    Lower = 2,
+    /// The real score is at most the stored one, no move raised alpha.
+    Upper = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableEntry {
+    /// Best or refuting move, [ShortMove::INVALID] if none is known.
+    pub best: ShortMove,
+    /// Score relative to the probing node, mates count from it.
+    pub score: i32,
+    pub depth: usize,
+    pub bound: Bound,
+}
+
+#[derive(Default)]
+struct Bucket([AtomicU64; ENTRIES_PER_BUCKET]);
+
+fn verification(key: u64) -> u64 {
+    key & 0xFFFF
+}
+
+fn entry_verification(data: u64) -> u64 {
+    data >> 48
+}
+
+fn entry_age(data: u64) -> u8 {
+    (data >> 42) as u8 & AGE_MASK
+}
+
+fn entry_depth(data: u64) -> i32 {
+    (data >> 32) as u8 as i32
+}
+
+/// Mates are stored relative to the node so they stay correct when reached through another path.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Shared hash table of search results. Entries are replaced by depth, preferring to overwrite
+/// those left from earlier searches.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// Table taking up about the size in MB, as given by the `Hash` option. Fails instead of
+    /// aborting when the memory can't be had.
+    pub fn new(hash_capacity: usize) -> anyhow::Result<Self> {
+        let len = (hash_capacity.saturating_mul(BYTES_PER_MB) / size_of::<Bucket>()).max(1);
+        let mut buckets = Vec::new();
+        buckets.try_reserve_exact(len).map_err(|_| anyhow!("cannot allocate a {hash_capacity} MB hash table"))?;
+        buckets.resize_with(len, Bucket::default);
+        Ok(Self { buckets, age: AtomicU8::new(0) })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    fn bucket(&self, key: u64) -> &Bucket {
+        &self.buckets[((key as u128 * self.buckets.len() as u128) >> 64) as usize]
+    }
+
+    /// Mark the start of a search, older entries become preferred for replacement.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed) & AGE_MASK
+    }
+
+    pub fn clear(&self) {
+        for bucket in self.buckets.iter() {
+            for entry in bucket.0.iter() {
+                entry.store(0, Ordering::Relaxed);
+            }
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TableEntry> {
+        let data = self.bucket(key).0.iter()
+            .map(|entry| entry.load(Ordering::Relaxed))
+            .find(|data| *data != 0 && entry_verification(*data) == verification(key))?;
+        let bound = match (data >> 40) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        Some(TableEntry {
+            best: ShortMove::from(data as u16),
+            score: score_from_table((data >> 16) as u16 as i16 as i32, ply),
+            depth: entry_depth(data) as usize,
+            bound,
+        })
+    }
+
+    pub fn store(&self, key: u64, ply: usize, entry: TableEntry) {
+        let age = self.age();
+        let bucket = self.bucket(key);
+        let mut victim = 0;
+        let mut victim_worth = i32::MAX;
+        let mut best = entry.best;
+        for (i, slot) in bucket.0.iter().enumerate() {
+            let data = slot.load(Ordering::Relaxed);
+            if data == 0 {
+                victim = i;
+                break;
+            }
+            if entry_verification(data) == verification(key) {
+                // Keep a deeper bound of this search rather than overwrite it with a shallow one.
+                if entry.bound != Bound::Exact && entry_age(data) == age && (entry.depth as i32) + 2 < entry_depth(data) {
+                    return;
+                }
+                if best == ShortMove::INVALID {
+                    best = ShortMove::from(data as u16);
+                }
+                victim = i;
+                break;
+            }
+            let worth = entry_depth(data) - AGE_WEIGHT * (age.wrapping_sub(entry_age(data)) & AGE_MASK) as i32;
+            if worth < victim_worth {
+                victim = i;
+                victim_worth = worth;
+            }
+        }
+        let score = score_to_table(entry.score, ply) as i16 as u16 as u64;
+        let data = *best as u64
+            | score << 16
+            | (entry.depth.min(u8::MAX as usize) as u64) << 32
+            | (entry.bound as u64) << 40
+            | (age as u64) << 42
+            | verification(key) << 48;
+        bucket.0[victim].store(data, Ordering::Relaxed);
+    }
+
+    /// Permille of a sample of entries used by the current search.
+    pub fn hashfull(&self) -> usize {
+        let age = self.age();
+        let sample = &self.buckets[..self.buckets.len().min(HASHFULL_SAMPLE)];
+        let used = sample.iter()
+            .flat_map(|bucket| bucket.0.iter())
+            .map(|entry| entry.load(Ordering::Relaxed))
+            .filter(|data| *data != 0 && entry_age(*data) == age)
+            .count();
+        used * 1000 / (sample.len() * ENTRIES_PER_BUCKET)
+    }
+}
+
+#[test]
+fn table_stores_and_replaces() {
+    use super::alphabeta::MATE_SCORE;
+
+    let board = chess_core::board::Board::default();
+    let e2e4 = board.parse_uci_move("e2e4").expect("e2e4 is legal");
+    let tt = TranspositionTable::new(1).expect("1 MB fits");
+    let key = board.hash();
+    assert_eq!(tt.probe(key, 0), None);
+
+    tt.store(key, 3, TableEntry { best: e2e4, score: MATE_SCORE - 5, depth: 6, bound: Bound::Exact });
+    let entry = tt.probe(key, 1).expect("entry should be found");
+    assert_eq!(entry, TableEntry { best: e2e4, score: MATE_SCORE - 3, depth: 6, bound: Bound::Exact }, "mates count from the probing node");
+    assert_eq!(tt.probe(key ^ (1 << 63), 0), None, "other keys fail verification");
+
+    // A shallow bound keeps the deeper entry, a new search lets it go.
+    tt.store(key, 0, TableEntry { best: ShortMove::INVALID, score: -20, depth: 1, bound: Bound::Upper });
+    assert_eq!(tt.probe(key, 0).map(|e| e.depth), Some(6));
+    tt.new_search();
+    tt.store(key, 0, TableEntry { best: ShortMove::INVALID, score: -20, depth: 1, bound: Bound::Upper });
+    assert_eq!(tt.probe(key, 0), Some(TableEntry { best: e2e4, score: -20, depth: 1, bound: Bound::Upper }), "the move is kept");
+
+    // Keys sharing the upper bits land in the same bucket and must still be told apart.
+    let neighbour = key ^ 1;
+    assert!(std::ptr::eq(tt.bucket(key), tt.bucket(neighbour)));
+    assert_eq!(tt.probe(neighbour, 0), None, "a key in the same bucket fails verification");
+    let big = TranspositionTable::new(64).expect("64 MB fits");
+    assert!(std::ptr::eq(big.bucket(key), big.bucket(neighbour)));
+    big.store(key, 0, TableEntry { best: e2e4, score: 10, depth: 2, bound: Bound::Exact });
+    assert_eq!(big.probe(neighbour, 0), None, "tables with 2^16 or more buckets still verify");
+
+    assert!(tt.hashfull() <= 1000);
+    tt.clear();
+    assert_eq!(tt.probe(key, 0), None);
+
+    assert!(TranspositionTable::new(usize::MAX).is_err(), "an impossible size is an error, not an abort");
+}
@@ -0,0 +1,37 @@
+//! Scripted engine for the client tests, it answers each command with fixed output and echoes
+//! `setoption` back as an `info string`. `ucinewgame` gets a malformed line for the client to report.
+
+use std::io::BufRead;
+
+fn main() {
+    // Engines often greet with a line that is not UCI.
+    println!("Fake Engine 1.0 by the chess-uci tests");
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        match line.split_ascii_whitespace().next() {
+            Some("uci") => {
+                println!("id name Fake Engine 1.0");
+                println!("id author The chess-uci Authors");
+                println!("option name Skill Level type spin default 20 min -20 max 20");
+                println!("option name Style type combo default Very Solid var Very Solid var Risky");
+                println!("option name Book File type string default <empty>");
+                println!("option name Clear Hash type button");
+                println!("option name Ponder type check default false");
+                println!("copyprotection checking");
+                println!("copyprotection ok");
+                println!("uciok");
+            },
+            Some("setoption") => println!("info string {line}"),
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => println!("info depth many"),
+            Some("go") => {
+                println!("info depth 1 score cp 20 nodes 21 ebf 1.8 pv e2e4");
+                println!("info string thinking hard");
+                println!("info depth 2 seldepth 3 multipv 1 score mate 2 lowerbound nodes 42 pv e2e4 e7e5");
+                println!("bestmove e2e4 ponder e7e5");
+            },
+            Some("quit") => break,
+            _ => {},
+        }
+    }
+}
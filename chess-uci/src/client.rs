@@ -0,0 +1,162 @@
+//! Drive an external UCI engine, ie. to match engines or analyse with a stronger one.
+
+use std::{ffi::OsStr, io::{BufRead, BufReader, Write}, process::{Child, ChildStdin, Command, ExitStatus, Stdio}, sync::mpsc::{self, Receiver, RecvTimeoutError}, thread, time::{Duration, Instant}};
+
+use anyhow::{bail, Context};
+
+use crate::{GoCommand, IdEvent, InfoEvent, OptionEvent, UciCommand, UciError, UciEvent, UciOption};
+
+/// How often `quit` checks whether the engine has exited.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Final answer to a `go` command.
+#[derive(Debug, PartialEq)]
+pub struct BestMove {
+    pub best: String,
+    pub ponder: Option<String>,
+}
+
+/// An engine process and what it reported during the `uci` handshake. The engine is killed when
+/// this is dropped without [EngineProcess::quit].
+pub struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines of the engine's stdout, read on their own thread so reads can time out.
+    lines: Receiver<String>,
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<OptionEvent>,
+}
+
+impl EngineProcess {
+    /// Start the engine and run the handshake, which fails if `uciok` does not come within the
+    /// timeout.
+    pub fn launch<S: AsRef<OsStr>>(program: impl AsRef<OsStr>, args: impl IntoIterator<Item = S>, timeout: Duration) -> anyhow::Result<Self> {
+        let program = program.as_ref();
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to start {}", program.to_string_lossy()))?;
+        let stdin = child.stdin.take().context("engine stdin is not piped")?;
+        let stdout = child.stdout.take().context("engine stdout is not piped")?;
+        let (sender, lines) = mpsc::channel();
+        thread::Builder::new().name("engine stdout".to_string()).spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        }).context("failed to spawn engine reader thread")?;
+
+        let mut engine = Self { child, stdin, lines, name: None, author: None, options: vec![] };
+        engine.send(&UciCommand::Uci)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            match engine.recv_until(deadline)? {
+                UciEvent::Id(IdEvent::Name(name)) => engine.name = Some(name),
+                UciEvent::Id(IdEvent::Author(author)) => engine.author = Some(author),
+                UciEvent::Option(option) => engine.options.push(option),
+                UciEvent::UciOk => break,
+                _ => {},
+            }
+        }
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Options in the order the engine reported them.
+    pub fn options(&self) -> &[OptionEvent] {
+        &self.options
+    }
+
+    pub fn option(&self, name: &str) -> Option<&OptionEvent> {
+        self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn send(&mut self, command: &UciCommand) -> anyhow::Result<()> {
+        writeln!(self.stdin, "{command}").and_then(|_| self.stdin.flush()).with_context(|| format!("failed to send {command:?}"))
+    }
+
+    /// Next event from the engine. Lines that are not UCI, such as banners, are skipped, while
+    /// malformed events are errors.
+    pub fn recv(&mut self, timeout: Duration) -> anyhow::Result<UciEvent> {
+        self.recv_until(Instant::now() + timeout)
+    }
+
+    fn recv_until(&mut self, deadline: Instant) -> anyhow::Result<UciEvent> {
+        loop {
+            let line = match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => bail!("timed out waiting for the engine"),
+                Err(RecvTimeoutError::Disconnected) => bail!("engine closed its output"),
+            };
+            match UciEvent::parse(line.clone()) {
+                Ok(event) => return Ok(event),
+                Err(UciError::UnknownCommand(_) | UciError::EmptyEvent) => {},
+                Err(e) => bail!("engine sent {line:?}: {e}"),
+            }
+        }
+    }
+
+    /// Send `isready` and wait for `readyok`, any events before it are dropped.
+    pub fn is_ready(&mut self, timeout: Duration) -> anyhow::Result<()> {
+        self.send(&UciCommand::IsReady)?;
+        let deadline = Instant::now() + timeout;
+        while self.recv_until(deadline)? != UciEvent::ReadyOk {}
+        Ok(())
+    }
+
+    pub fn set_option(&mut self, name: &str, value: Option<&str>) -> anyhow::Result<()> {
+        self.send(&UciCommand::SetOption(UciOption::new(name, value)))
+    }
+
+    /// Search and wait for the best move, passing on the engine's `info` lines.
+    pub fn go(&mut self, go: GoCommand, timeout: Duration, mut on_info: impl FnMut(&InfoEvent)) -> anyhow::Result<BestMove> {
+        self.send(&UciCommand::Go(go))?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.recv_until(deadline)? {
+                UciEvent::Info(info) => on_info(&info),
+                UciEvent::BestMove { best, ponder } => return Ok(BestMove { best, ponder }),
+                _ => {},
+            }
+        }
+    }
+
+    /// Send `quit` and wait for the engine to exit, killing it if it does not in time.
+    pub fn quit(mut self, timeout: Duration) -> anyhow::Result<ExitStatus> {
+        // An engine that already exited can't read it, which waiting below reports.
+        let _ = self.send(&UciCommand::Quit);
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(status);
+            }
+            if Instant::now() >= deadline {
+                self.child.kill()?;
+                self.child.wait()?;
+                bail!("engine did not quit in time and was killed");
+            }
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
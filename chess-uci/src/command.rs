@@ -0,0 +1,472 @@
+use std::{fmt::Display, iter::Peekable, str::SplitAsciiWhitespace};
+
+use chess_core::{board::{fen::NUM_FEN_FIELDS, Board}, game::GameState};
+
+use crate::UciError;
+
+/// Inputs to the engine.
+#[derive(Debug, PartialEq)]
+pub enum UciCommand {
+    /// Tell engine to use UCI. Engine must identify itself with [UciEvent::Id] and send [UciEvent::Option]
+    /// to inform the GUI what settings the engine supports. Finally the engine should send
+    /// [UciEvent::UciOk] to acknoledge UCI mode.
+    Uci,
+    /// Enable/disable debug info being sent to the GUI via [UciEvent::Info].
+    Debug(bool),
+    /// Syncronize the engine and GUI. Engine should respond with [UciEvent::ReadyOk].
+    IsReady,
+    /// Change an engine setting.
+    SetOption(UciOption),
+    /// Register the engine with a name and code, or later.
+    Register(RegisterCommand),
+    /// Engine should prepare to evaluate a different game than the current one.
+    UciNewGame,
+    /// Set the engine to evaluate the given position. Could be a ply down from the currently held
+    /// position, or an entirely different game. In the later case, a "ucinewgame" is prefered but
+    /// not always given by the GUI.
+    Position(PositionCommand),
+    /// Start calculating on the current position.
+    Go(GoCommand),
+    /// Stop current search ASAP. Engine should send "bestmove" and "ponder" if able.
+    Stop,
+    /// User has played the expected "ponder" move. Continue ponder search as a normal search.
+    PonderHit,
+    /// Terminate the engine ASAP.
+    Quit,
+    /// Non-standard `d`: print the current position.
+    PrintBoard,
+    /// Non-standard `eval`: print the static evaluation of the current position term by term.
+    Eval,
+    /// Non-standard `flip`: swap the sides of the current position.
+    Flip,
+    /// Non-standard `bench`: search a fixed set of positions and report the nodes and speed.
+    Bench(BenchCommand),
+}
+
+impl Display for UciCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cmd = match self {
+            UciCommand::Uci => "uci".to_string(),
+            UciCommand::Debug(on) => format!("debug {}", if *on { "on" } else { "off" }),
+            UciCommand::IsReady => "isready".to_string(),
+            UciCommand::SetOption(option) => option.to_string(),
+            UciCommand::Register(register) => register.to_string(),
+            UciCommand::UciNewGame => "ucinewgame".to_string(),
+            UciCommand::Position(pos_cmd) => match pos_cmd {
+                PositionCommand::StartPos { moves } => match moves {
+                    Some(mv) => format!("position startpos moves {}", mv.join(" ")),
+                    None => "position startpos".to_string(),
+                },
+                PositionCommand::Fen { fen, moves } => match moves {
+                    Some(mv) => format!("position fen {} moves {}", fen, mv.join(" ")),
+                    None => format!("position fen {}", fen),
+                },
+            },
+            UciCommand::Go(go_cmd) if go_cmd.kind.is_none() && go_cmd.params.is_empty() => "go".to_string(),
+            UciCommand::Go(go_cmd) => format!("go {go_cmd}"),
+            UciCommand::Stop => "stop".to_string(),
+            UciCommand::PonderHit => "ponderhit".to_string(),
+            UciCommand::Quit => "quit".to_string(),
+            UciCommand::PrintBoard => "d".to_string(),
+            UciCommand::Eval => "eval".to_string(),
+            UciCommand::Flip => "flip".to_string(),
+            UciCommand::Bench(bench) => bench.to_string(),
+        };
+        write!(f, "{}", cmd)
+    }
+}
+
+impl UciCommand {
+    /// Names of the commands, which start a line.
+    const NAMES: [&str; 15] = [
+        "uci", "debug", "isready", "setoption", "register", "ucinewgame", "position", "go", "stop", "ponderhit", "quit",
+        "d", "eval", "flip", "bench",
+    ];
+
+    /// Parse a line from the GUI. As the protocol asks, unknown tokens before the command and
+    /// within `go` are skipped, ie. "joho debug on" turns debug mode on.
+    pub fn parse(s: &str) -> Result<Self, UciError> {
+        let mut parts = s.split_ascii_whitespace();
+        if s.trim().is_empty() {
+            return Err(UciError::EmptyCommand);
+        }
+        let command = parts.by_ref().find(|part| Self::NAMES.contains(part)).ok_or_else(|| UciError::UnknownCommand(s.trim().to_string()))?;
+        Ok(match command {
+            "uci" => UciCommand::Uci,
+            "debug" => UciCommand::Debug(match parts.next() {
+                Some("on") => true,
+                Some("off") => false,
+                Some(_) => return Err(UciError::InvalidParameter),
+                None => return Err(UciError::MissingValue),
+            }),
+            "isready" => UciCommand::IsReady,
+            "setoption" => UciCommand::SetOption(UciOption::parse(&mut parts)?),
+            "register" => UciCommand::Register(RegisterCommand::parse(&mut parts)?),
+            "ucinewgame" => UciCommand::UciNewGame,
+            "position" => UciCommand::Position(PositionCommand::parse(&mut parts)?),
+            "go" => UciCommand::Go(GoCommand::parse(&mut parts.by_ref().peekable())?),
+            "stop" => UciCommand::Stop,
+            "ponderhit" => UciCommand::PonderHit,
+            "quit" => UciCommand::Quit,
+            "d" => UciCommand::PrintBoard,
+            "eval" => UciCommand::Eval,
+            "flip" => UciCommand::Flip,
+            _ => UciCommand::Bench(BenchCommand::parse(&mut parts)?),
+        })
+    }
+
+    /// Whether the command is one of the debugging commands outside the protocol, which engines
+    /// may want to turn off for strict GUIs.
+    pub fn is_extension(&self) -> bool {
+        matches!(self, UciCommand::PrintBoard | UciCommand::Eval | UciCommand::Flip | UciCommand::Bench(_) | UciCommand::Go(GoCommand { kind: Some(GoKind::Perft(_)), .. }))
+    }
+}
+
+/// A `setoption` command, the engine checks the name and value against the options it reported.
+#[derive(Debug, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    /// `None` for buttons.
+    pub value: Option<String>,
+}
+
+impl UciOption {
+    pub fn new(name: impl Display, value: Option<impl Display>) -> Self {
+        Self { name: name.to_string(), value: value.map(|value| value.to_string()) }
+    }
+
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        match parts.next() {
+            Some("name") => {},
+            Some(_) => return Err(UciError::InvalidParameter),
+            None => return Err(UciError::MissingParameter),
+        }
+        // Names may have spaces, ie. "Move Overhead", and run up to "value". Values may have
+        // spaces too and run to the end of the line.
+        let mut name = vec![];
+        let mut value = None;
+        while let Some(part) = parts.next() {
+            if part == "value" {
+                value = Some(parts.by_ref().collect::<Vec<_>>().join(" "));
+                break;
+            }
+            name.push(part);
+        }
+        if name.is_empty() {
+            return Err(UciError::MissingParameter);
+        }
+        if value.as_ref().is_some_and(String::is_empty) {
+            return Err(UciError::MissingValue);
+        }
+        Ok(UciOption { name: name.join(" "), value })
+    }
+}
+
+impl Display for UciOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "setoption name {}", self.name)?;
+        if let Some(value) = &self.value {
+            write!(f, " value {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RegisterCommand {
+    /// Register at a later time.
+    Later,
+    /// Register with the user's name and code, both may have spaces.
+    Now { name: String, code: String },
+}
+
+impl RegisterCommand {
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        let mut name = vec![];
+        let mut code = vec![];
+        let mut field = None;
+        for part in parts {
+            match part {
+                "later" if field.is_none() => return Ok(RegisterCommand::Later),
+                "name" => field = Some(&mut name),
+                "code" => field = Some(&mut code),
+                _ => field.as_mut().ok_or(UciError::InvalidParameter)?.push(part),
+            }
+        }
+        if name.is_empty() && code.is_empty() {
+            return Err(UciError::MissingParameter);
+        }
+        Ok(RegisterCommand::Now { name: name.join(" "), code: code.join(" ") })
+    }
+}
+
+impl Display for RegisterCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterCommand::Later => write!(f, "register later"),
+            RegisterCommand::Now { name, code } => write!(f, "register name {name} code {code}"),
+        }
+    }
+}
+
+/// `bench [depth] [threads] [hash]`, the engine picks defaults for the ones left out.
+#[derive(Debug, Default, PartialEq)]
+pub struct BenchCommand {
+    pub depth: Option<usize>,
+    pub threads: Option<usize>,
+    /// Size of the hash table in MB.
+    pub hash: Option<usize>,
+}
+
+impl BenchCommand {
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        let mut numbers = [None; 3];
+        for (i, part) in parts.enumerate() {
+            let number = numbers.get_mut(i).ok_or(UciError::ExcessiveCommandArgs)?;
+            *number = Some(part.parse::<usize>().map_err(|_| UciError::ParseError)?);
+        }
+        let [depth, threads, hash] = numbers;
+        Ok(BenchCommand { depth, threads, hash })
+    }
+}
+
+impl Display for BenchCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bench")?;
+        // Later arguments can only be given with the earlier ones.
+        for number in [self.depth, self.threads, self.hash].into_iter().map_while(|number| number) {
+            write!(f, " {number}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PositionCommand {
+    StartPos { moves: Option<Vec<String>> },
+    Fen {
+        fen: String,
+        moves: Option<Vec<String>>,
+    },
+}
+
+impl PositionCommand {
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        Ok(match parts.next() {
+            Some("startpos") => {
+                // Tokens before "moves" are not part of the command.
+                let moves = parts.by_ref().find(|part| *part == "moves").map(|_| parts.map(|p| p.to_string()).collect());
+                PositionCommand::StartPos { moves }
+            },
+            Some("fen") => {
+                // Take fields up to "moves" so FENs without the move counters are accepted.
+                let mut fields = Vec::with_capacity(NUM_FEN_FIELDS);
+                let mut moves: Option<Vec<String>> = None;
+                while let Some(part) = parts.next() {
+                    if part == "moves" {
+                        moves = Some(parts.by_ref().map(|p| p.to_string()).collect());
+                        break;
+                    }
+                    fields.push(part);
+                }
+                if fields.is_empty() {
+                    return Err(UciError::MissingValue);
+                }
+                if fields.len() == NUM_FEN_FIELDS - 2 {
+                    fields.extend(["0", "1"]);
+                }
+                PositionCommand::Fen { fen: fields.join(" "), moves }
+            },
+            Some(_) => return Err(UciError::InvalidParameter),
+            None => return Err(UciError::MissingParameter),
+        })
+    }
+
+    /// Load the position and play its moves, checking each one is legal.
+    pub fn to_game(&self) -> Result<GameState, UciError> {
+        let (board, moves) = match self {
+            PositionCommand::StartPos { moves } => (Board::default(), moves),
+            PositionCommand::Fen { fen, moves } => {
+                let mut board = Board::new();
+                board.load_fen(fen).map_err(|_| UciError::InvalidFen(fen.clone()))?;
+                (board, moves)
+            },
+        };
+        let mut game = GameState::new(board);
+        for notation in moves.iter().flatten() {
+            let m = game.board().parse_uci_move(notation).map_err(|_| UciError::IllegalMove(notation.clone()))?;
+            game.play(m);
+        }
+        Ok(game)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GoCommand {
+    /// `None` when the search is only limited by the clock parameters, or not at all.
+    pub kind: Option<GoKind>,
+    pub params: Vec<GoParam>,
+}
+
+impl Display for GoCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(kind) = &self.kind {
+            parts.push(kind.to_string());
+        }
+        parts.extend(self.params.iter().map(GoParam::to_string));
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl GoCommand {
+    /// Keywords of a `go` command.
+    const KEYWORDS: [&str; 13] = [
+        "searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite",
+        "perft",
+    ];
+
+    fn parse(parts: &mut Peekable<&mut SplitAsciiWhitespace>) -> Result<Self, UciError> {
+        let mut kind = None;
+        let mut params = vec![];
+        let number = |parts: &mut Peekable<&mut SplitAsciiWhitespace>| parts.next().ok_or(UciError::MissingValue)?.parse::<usize>().map_err(|_| UciError::ParseError);
+        // GUIs may send a negative clock once it runs out.
+        let millis = |parts: &mut Peekable<&mut SplitAsciiWhitespace>| parts.next().ok_or(UciError::MissingValue)?.parse::<i64>().map(|ms| ms.max(0) as usize).map_err(|_| UciError::ParseError);
+        while let Some(part) = parts.next() {
+            let param = match part {
+                "infinite" => {
+                    kind = Some(GoKind::Infinite);
+                    continue;
+                },
+                "depth" => {
+                    kind = Some(GoKind::Depth(number(parts)?));
+                    continue;
+                },
+                "nodes" => {
+                    kind = Some(GoKind::Nodes(number(parts)?));
+                    continue;
+                },
+                "perft" => {
+                    kind = Some(GoKind::Perft(number(parts)?));
+                    continue;
+                },
+                "mate" => {
+                    kind = Some(GoKind::Mate(number(parts)?));
+                    continue;
+                },
+                "ponder" => GoParam::Ponder,
+                "wtime" => GoParam::WTime(millis(parts)?),
+                "btime" => GoParam::BTime(millis(parts)?),
+                "winc" => GoParam::WInc(millis(parts)?),
+                "binc" => GoParam::BInc(millis(parts)?),
+                "movestogo" => GoParam::MovesToGo(number(parts)?),
+                "movetime" => GoParam::MoveTime(millis(parts)?),
+                // Moves run up to the next keyword.
+                "searchmoves" => {
+                    let mut moves = vec![];
+                    while let Some(m) = parts.next_if(|part| !Self::KEYWORDS.contains(part)) {
+                        moves.push(m.to_string());
+                    }
+                    GoParam::SearchMoves(moves)
+                },
+                _ => continue,
+            };
+            params.push(param);
+        }
+        Ok(GoCommand { kind, params })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GoKind {
+    Depth(usize),
+    Nodes(usize),
+    Mate(usize),
+    Infinite,
+    /// Non-standard: count the leaf nodes below each legal move to the depth.
+    Perft(usize),
+}
+
+impl Display for GoKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoKind::Depth(n) => write!(f, "depth {n}"),
+            GoKind::Nodes(n) => write!(f, "nodes {n}"),
+            GoKind::Mate(n) => write!(f, "mate {n}"),
+            GoKind::Infinite => write!(f, "infinite"),
+            GoKind::Perft(n) => write!(f, "perft {n}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GoParam {
+    SearchMoves(Vec<String>),
+    WTime(usize),
+    BTime(usize),
+    WInc(usize),
+    BInc(usize),
+    MovesToGo(usize),
+    MoveTime(usize),
+    Ponder,
+}
+
+impl Display for GoParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoParam::SearchMoves(m) => write!(f, "searchmoves {}", m.join(" ")),
+            GoParam::WTime(n) => write!(f, "wtime {n}"),
+            GoParam::BTime(n) => write!(f, "btime {n}"),
+            GoParam::WInc(n) => write!(f, "winc {n}"),
+            GoParam::BInc(n) => write!(f, "binc {n}"),
+            GoParam::MovesToGo(n) => write!(f, "movestogo {n}"),
+            GoParam::MoveTime(n) => write!(f, "movetime {n}"),
+            GoParam::Ponder => write!(f, "ponder"),
+        }
+    }
+}
+
+#[test]
+fn parse_survives_random_input() {
+    const TOKENS: [&str; 45] = [
+        "uci", "debug", "on", "off", "isready", "setoption", "name", "value", "type", "register", "later", "code",
+        "ucinewgame", "position", "startpos", "fen", "moves", "go", "searchmoves", "ponder", "wtime", "btime", "perft", "d", "eval", "flip", "bench",
+        "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite", "stop", "ponderhit", "quit",
+        "e2e4", "0", "-1", "18446744073709551616", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", "joho", "ü",
+    ];
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut random = |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % n
+    };
+    for _ in 0..20_000 {
+        let len = random(10);
+        let line = (0..len).map(|_| TOKENS[random(TOKENS.len())]).collect::<Vec<_>>().join(" ");
+        // Whatever parses must be written back as the same command.
+        if let Ok(command) = UciCommand::parse(&line) {
+            let written = command.to_string();
+            assert_eq!(UciCommand::parse(&written).ok(), Some(command), "{line:?} was written as {written:?}");
+        }
+    }
+
+    assert_eq!(UciCommand::parse("joho debug on").ok(), Some(UciCommand::Debug(true)));
+    assert_eq!(UciCommand::parse("go depth 5 blah wtime -30").ok(), Some(UciCommand::Go(GoCommand {
+        kind: Some(GoKind::Depth(5)),
+        params: vec![GoParam::WTime(0)],
+    })));
+    assert_eq!(UciCommand::parse("register name Stefan MK code 4359874324").ok(), Some(UciCommand::Register(RegisterCommand::Now {
+        name: "Stefan MK".to_string(),
+        code: "4359874324".to_string(),
+    })));
+    assert!(matches!(UciCommand::parse("   "), Err(UciError::EmptyCommand)));
+    assert!(matches!(UciCommand::parse("joho"), Err(UciError::UnknownCommand(_))));
+
+    for line in ["d", "eval", "flip", "go perft 5", "bench", "bench 10 4 256"] {
+        let command = UciCommand::parse(line).expect("debug commands parse");
+        assert!(command.is_extension(), "{line}");
+        assert_eq!(command.to_string(), line);
+    }
+    assert!(!UciCommand::parse("go depth 5").expect("go parses").is_extension());
+}
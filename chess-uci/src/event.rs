@@ -0,0 +1,692 @@
+use std::{fmt::Display, str::SplitAsciiWhitespace};
+
+use crate::UciError;
+
+/// Outputs from the engine.
+#[derive(Debug, PartialEq)]
+pub enum UciEvent {
+    /// Sent to the GUI after receiving a [UciCommand::Uci] to self-identify the engine.
+    Id(IdEvent),
+    /// Acknoledge [UciCommand::Uci].
+    UciOk,
+    /// Acknoledge [UciCommand::IsReady] as soon as engine is ready to accept new commands.
+    ReadyOk,
+    /// Report the results of a search. Does not start pondering automatically.
+    BestMove {
+        /// Most advantageous move to make.
+        best: String,
+        /// Next move that the engine would like to continue working on.
+        ponder: Option<String>,
+    },
+    /// Report the state of the engine's copy protection check.
+    CopyProtection(CheckStatus),
+    /// Report the state of the engine's registration, the GUI may then send [UciCommand::Register].
+    ///
+    /// [UciCommand::Register]: crate::UciCommand::Register
+    Registration(CheckStatus),
+    /// Report some information to the GUI.
+    Info(Box<InfoEvent>),
+    /// Inform the GUI of what engine settings can be changed.
+    Option(OptionEvent),
+}
+
+impl Display for UciEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            UciEvent::Id(id_event) => match id_event {
+                IdEvent::Name(name) => format!("id name {}", name),
+                IdEvent::Author(author) => format!("id author {}", author),
+            },
+            UciEvent::UciOk => "uciok".to_string(),
+            UciEvent::ReadyOk => "readyok".to_string(),
+            UciEvent::BestMove { best, ponder } => match ponder {
+                None => format!("bestmove {best}"),
+                Some(ponder) => format!("bestmove {best} ponder {ponder}")
+            },
+            UciEvent::CopyProtection(status) => format!("copyprotection {status}"),
+            UciEvent::Registration(status) => format!("registration {status}"),
+            UciEvent::Info(info_event) => format!("info {info_event}"),
+            UciEvent::Option(opt_event) => format!("option {opt_event}"),
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl UciEvent {
+    pub fn parse(line: String) -> Result<Self, UciError> {
+        let mut parts = line.split_ascii_whitespace();
+        Ok(match parts.next() {
+            // Names and authors may have spaces and run to the end of the line.
+            Some("id") => match (parts.next(), parts.collect::<Vec<_>>().join(" ")) {
+                (Some(_), value) if value.is_empty() => return Err(UciError::MissingValue),
+                (Some("name"), name) => UciEvent::Id(IdEvent::Name(name)),
+                (Some("author"), author) => UciEvent::Id(IdEvent::Author(author)),
+                (Some(_), _) => return Err(UciError::InvalidParameter),
+                (None, _) => return Err(UciError::MissingParameter),
+            },
+            Some("uciok") => UciEvent::UciOk,
+            Some("readyok") => UciEvent::ReadyOk,
+            Some("bestmove") => {
+                let best = parts.next().ok_or(UciError::MissingValue)?.to_string();
+                let ponder = match parts.next() {
+                    Some("ponder") => Some(parts.next().ok_or(UciError::MissingValue)?.to_string()),
+                    Some(_) => return Err(UciError::InvalidParameter),
+                    None => None,
+                };
+                UciEvent::BestMove { best, ponder }
+            },
+            Some("copyprotection") => UciEvent::CopyProtection(CheckStatus::parse(parts.next())?),
+            Some("registration") => UciEvent::Registration(CheckStatus::parse(parts.next())?),
+            Some("info") => UciEvent::Info(Box::new(InfoEvent::parse(&mut parts)?)),
+            Some("option") => UciEvent::Option(OptionEvent::parse(&mut parts)?),
+            Some(_) => return Err(UciError::UnknownCommand(line.trim().to_string())),
+            None => return Err(UciError::EmptyEvent),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum IdEvent {
+    Name(String),
+    Author(String),
+}
+
+/// State of a copy protection or registration check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Checking,
+    Ok,
+    Error,
+}
+
+impl CheckStatus {
+    fn parse(part: Option<&str>) -> Result<Self, UciError> {
+        match part {
+            Some("checking") => Ok(CheckStatus::Checking),
+            Some("ok") => Ok(CheckStatus::Ok),
+            Some("error") => Ok(CheckStatus::Error),
+            Some(_) => Err(UciError::InvalidParameter),
+            None => Err(UciError::MissingValue),
+        }
+    }
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckStatus::Checking => write!(f, "checking"),
+            CheckStatus::Ok => write!(f, "ok"),
+            CheckStatus::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Fields of an `info` line, all optional. Build one with the setters, ie.
+/// `InfoEvent::default().depth(5).score(ScoreEvent::cp(20))`.
+#[derive(Debug, Default, PartialEq)]
+pub struct InfoEvent {
+    pub depth: Option<usize>,
+    pub sel_depth: Option<usize>,
+    pub time: Option<usize>,
+    pub nodes: Option<usize>,
+    pub pv: Vec<Box<str>>,
+    pub multi_pv: Option<usize>,
+    pub score: Option<ScoreEvent>,
+    /// Win, draw and loss chances in permille, sent after the score with `UCI_ShowWDL`.
+    pub wdl: Option<WdlEvent>,
+    pub curr_move: Option<Box<str>>,
+    pub curr_move_number: Option<usize>,
+    /// Permille of the hash table in use.
+    pub hash_full: Option<usize>,
+    pub nps: Option<usize>,
+    pub tb_hits: Option<usize>,
+    pub sb_hits: Option<usize>,
+    /// Permille of CPU usage.
+    pub cpu_load: Option<usize>,
+    /// A move followed by the line refuting it.
+    pub refutation: Vec<Box<str>>,
+    /// Line searched by a thread, the thread is optional.
+    pub curr_line: Option<(Option<usize>, Vec<Box<str>>)>,
+    pub string: Box<str>,
+}
+
+impl InfoEvent {
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn sel_depth(mut self, sel_depth: usize) -> Self {
+        self.sel_depth = Some(sel_depth);
+        self
+    }
+
+    /// Milliseconds searched.
+    pub fn time(mut self, time: usize) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    pub fn pv(mut self, pv: impl IntoIterator<Item = impl Display>) -> Self {
+        self.pv = pv.into_iter().map(|m| m.to_string().into_boxed_str()).collect();
+        self
+    }
+
+    pub fn multi_pv(mut self, multi_pv: usize) -> Self {
+        self.multi_pv = Some(multi_pv);
+        self
+    }
+
+    pub fn score(mut self, score: ScoreEvent) -> Self {
+        self.score = Some(score);
+        self
+    }
+
+    pub fn wdl(mut self, wdl: WdlEvent) -> Self {
+        self.wdl = Some(wdl);
+        self
+    }
+
+    pub fn curr_move(mut self, curr_move: impl Display) -> Self {
+        self.curr_move = Some(curr_move.to_string().into_boxed_str());
+        self
+    }
+
+    pub fn curr_move_number(mut self, curr_move_number: usize) -> Self {
+        self.curr_move_number = Some(curr_move_number);
+        self
+    }
+
+    pub fn hash_full(mut self, hash_full: usize) -> Self {
+        self.hash_full = Some(hash_full);
+        self
+    }
+
+    pub fn nps(mut self, nps: usize) -> Self {
+        self.nps = Some(nps);
+        self
+    }
+
+    pub fn tb_hits(mut self, tb_hits: usize) -> Self {
+        self.tb_hits = Some(tb_hits);
+        self
+    }
+
+    pub fn sb_hits(mut self, sb_hits: usize) -> Self {
+        self.sb_hits = Some(sb_hits);
+        self
+    }
+
+    pub fn cpu_load(mut self, cpu_load: usize) -> Self {
+        self.cpu_load = Some(cpu_load);
+        self
+    }
+
+    pub fn refutation(mut self, refutation: impl IntoIterator<Item = impl Display>) -> Self {
+        self.refutation = refutation.into_iter().map(|m| m.to_string().into_boxed_str()).collect();
+        self
+    }
+
+    pub fn curr_line(mut self, cpu: Option<usize>, line: impl IntoIterator<Item = impl Display>) -> Self {
+        self.curr_line = Some((cpu, line.into_iter().map(|m| m.to_string().into_boxed_str()).collect()));
+        self
+    }
+
+    pub fn string(mut self, string: impl Display) -> Self {
+        self.string = string.to_string().into_boxed_str();
+        self
+    }
+
+    /// Parse the fields following `info`.
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        let mut parts = parts.peekable();
+        let mut info = InfoEvent::default();
+        let number = |part: Option<&str>| part.ok_or(UciError::MissingValue)?.parse::<usize>().map_err(|_| UciError::ParseError);
+        // Moves run up to the next field.
+        let moves = |parts: &mut std::iter::Peekable<&mut SplitAsciiWhitespace>| {
+            let mut moves = vec![];
+            while let Some(m) = parts.next_if(|part| !INFO_FIELDS.contains(part)) {
+                moves.push(m.to_string().into_boxed_str());
+            }
+            moves
+        };
+        while let Some(part) = parts.next() {
+            match part {
+                "depth" => info.depth = Some(number(parts.next())?),
+                "seldepth" => info.sel_depth = Some(number(parts.next())?),
+                "time" => info.time = Some(number(parts.next())?),
+                "nodes" => info.nodes = Some(number(parts.next())?),
+                "pv" => info.pv = moves(&mut parts),
+                "multipv" => info.multi_pv = Some(number(parts.next())?),
+                "score" => info.score = Some(ScoreEvent::parse(&mut parts)?),
+                "wdl" => {
+                    let win = number(parts.next())?;
+                    let draw = number(parts.next())?;
+                    let loss = number(parts.next())?;
+                    info.wdl = Some(WdlEvent { win, draw, loss });
+                },
+                "currmove" => info.curr_move = Some(parts.next().ok_or(UciError::MissingValue)?.to_string().into_boxed_str()),
+                "currmovenumber" => info.curr_move_number = Some(number(parts.next())?),
+                "hashfull" => info.hash_full = Some(number(parts.next())?),
+                "nps" => info.nps = Some(number(parts.next())?),
+                "tbhits" => info.tb_hits = Some(number(parts.next())?),
+                "sbhits" => info.sb_hits = Some(number(parts.next())?),
+                "cpuload" => info.cpu_load = Some(number(parts.next())?),
+                "refutation" => info.refutation = moves(&mut parts),
+                "currline" => {
+                    let cpu = parts.next_if(|part| part.parse::<usize>().is_ok()).map(|cpu| number(Some(cpu))).transpose()?;
+                    info.curr_line = Some((cpu, moves(&mut parts)));
+                },
+                "string" => {
+                    info.string = parts.by_ref().collect::<Vec<_>>().join(" ").into_boxed_str();
+                },
+                // Engines add their own fields, which the protocol says to ignore.
+                _ => {},
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// Keywords of an `info` line.
+const INFO_FIELDS: [&str; 18] = [
+    "depth", "seldepth", "time", "nodes", "pv", "multipv", "score", "wdl", "currmove", "currmovenumber",
+    "hashfull", "nps", "tbhits", "sbhits", "cpuload", "string", "refutation", "currline",
+];
+
+impl Display for InfoEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields = vec![];
+        let mut field = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                fields.push(format!("{name} {value}"));
+            }
+        };
+        let moves = |moves: &[Box<str>]| (!moves.is_empty()).then(|| moves.join(" "));
+        field("depth", self.depth.map(|n| n.to_string()));
+        field("seldepth", self.sel_depth.map(|n| n.to_string()));
+        field("time", self.time.map(|n| n.to_string()));
+        field("nodes", self.nodes.map(|n| n.to_string()));
+        field("multipv", self.multi_pv.map(|n| n.to_string()));
+        field("score", self.score.as_ref().map(ScoreEvent::to_string));
+        field("wdl", self.wdl.as_ref().map(WdlEvent::to_string));
+        field("currmove", self.curr_move.as_ref().map(|m| m.to_string()));
+        field("currmovenumber", self.curr_move_number.map(|n| n.to_string()));
+        field("hashfull", self.hash_full.map(|n| n.to_string()));
+        field("nps", self.nps.map(|n| n.to_string()));
+        field("tbhits", self.tb_hits.map(|n| n.to_string()));
+        field("sbhits", self.sb_hits.map(|n| n.to_string()));
+        field("cpuload", self.cpu_load.map(|n| n.to_string()));
+        field("refutation", moves(&self.refutation));
+        field("currline", self.curr_line.as_ref().map(|(cpu, line)| match cpu {
+            Some(cpu) => format!("{cpu} {}", line.join(" ")),
+            None => line.join(" "),
+        }));
+        // The pv and string run to the end of the line.
+        field("pv", moves(&self.pv));
+        field("string", (!self.string.is_empty()).then(|| self.string.to_string()));
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+/// Score from the engine's point of view, in centipawns or moves to mate, negative when the engine
+/// is getting mated.
+#[derive(Debug, PartialEq)]
+pub struct ScoreEvent {
+    pub cp: Option<i32>,
+    pub mate: Option<i32>,
+    pub bound: ScoreEventBound,
+}
+
+impl ScoreEvent {
+    pub fn cp(cp: i32) -> Self {
+        Self { cp: Some(cp), mate: None, bound: ScoreEventBound::None }
+    }
+
+    pub fn mate(mate: i32) -> Self {
+        Self { cp: None, mate: Some(mate), bound: ScoreEventBound::None }
+    }
+
+    pub fn bound(mut self, bound: ScoreEventBound) -> Self {
+        self.bound = bound;
+        self
+    }
+
+    fn parse(parts: &mut std::iter::Peekable<&mut SplitAsciiWhitespace>) -> Result<Self, UciError> {
+        let mut score = ScoreEvent { cp: None, mate: None, bound: ScoreEventBound::None };
+        let value = |part: Option<&str>| part.ok_or(UciError::MissingValue)?.parse::<i32>().map_err(|_| UciError::ParseError);
+        while let Some(part) = parts.next_if(|part| ["cp", "mate", "lowerbound", "upperbound"].contains(part)) {
+            match part {
+                "cp" => score.cp = Some(value(parts.next())?),
+                "mate" => score.mate = Some(value(parts.next())?),
+                "lowerbound" => score.bound = ScoreEventBound::Lower,
+                _ => score.bound = ScoreEventBound::Upper,
+            }
+        }
+        if score.cp.is_none() && score.mate.is_none() {
+            return Err(UciError::MissingValue);
+        }
+        Ok(score)
+    }
+}
+
+impl Display for ScoreEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(cp) = self.cp {
+            parts.push(format!("cp {cp}"));
+        }
+        if let Some(mate) = self.mate {
+            parts.push(format!("mate {mate}"));
+        }
+        match self.bound {
+            ScoreEventBound::None => {},
+            ScoreEventBound::Upper => parts.push("upperbound".to_string()),
+            ScoreEventBound::Lower => parts.push("lowerbound".to_string()),
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScoreEventBound {
+    None,
+    Upper,
+    Lower,
+}
+
+/// Expected outcome in permille, the three add up to 1000.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WdlEvent {
+    pub win: usize,
+    pub draw: usize,
+    pub loss: usize,
+}
+
+impl Display for WdlEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.win, self.draw, self.loss)
+    }
+}
+
+/// A setting the engine offers, sent after `id` in reply to `uci`. Names may have spaces and run
+/// up to `type`.
+#[derive(Debug, PartialEq)]
+pub struct OptionEvent {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+impl OptionEvent {
+    pub fn new(name: impl Display, kind: OptionKind) -> Self {
+        Self { name: name.to_string(), kind }
+    }
+
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        match parts.next() {
+            Some("name") => {},
+            Some(_) => return Err(UciError::InvalidParameter),
+            None => return Err(UciError::MissingParameter),
+        }
+        let mut name = vec![];
+        loop {
+            match parts.next() {
+                Some("type") => break,
+                Some(part) => name.push(part),
+                None => return Err(UciError::MissingParameter),
+            }
+        }
+        if name.is_empty() {
+            return Err(UciError::MissingValue);
+        }
+        let kind = match parts.next().ok_or(UciError::MissingValue)? {
+            "check" => OptionKind::Check(Check::parse(parts)?),
+            "spin" => OptionKind::Spin(Spin::parse(parts)?),
+            "combo" => OptionKind::Combo(Combo::parse(parts)?),
+            "button" => OptionKind::Button,
+            "string" => OptionKind::String(Text::parse(parts)?),
+            _ => return Err(UciError::InvalidParameter),
+        };
+        Ok(OptionEvent { name: name.join(" "), kind })
+    }
+}
+
+impl Display for OptionEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "name {} type ", self.name)?;
+        match &self.kind {
+            OptionKind::Check(check) => write!(f, "check {check}"),
+            OptionKind::Spin(spin) => write!(f, "spin {spin}"),
+            OptionKind::Combo(combo) => write!(f, "combo {combo}"),
+            OptionKind::Button => write!(f, "button"),
+            OptionKind::String(text) => write!(f, "string {text}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionKind {
+    Check(Check),
+    Spin(Spin),
+    Combo(Combo),
+    /// Takes no value, setting it triggers an action.
+    Button,
+    String(Text),
+}
+
+/// Take the value of each keyword, values may have spaces and run up to the next keyword.
+fn keyword_values<'a>(parts: &mut SplitAsciiWhitespace<'a>, keywords: &[&str]) -> Result<Vec<(&'a str, String)>, UciError> {
+    let mut values: Vec<(&str, Vec<&str>)> = vec![];
+    for part in parts {
+        match values.last_mut() {
+            _ if keywords.contains(&part) => values.push((part, vec![])),
+            Some((_, value)) => value.push(part),
+            None => return Err(UciError::InvalidParameter),
+        }
+    }
+    Ok(values.into_iter().map(|(keyword, value)| (keyword, value.join(" "))).collect())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spin {
+    pub default: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Spin {
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        let (mut default, mut min, mut max) = (None, None, None);
+        for (keyword, value) in keyword_values(parts, &["default", "min", "max"])? {
+            let value = Some(value.parse::<i64>().map_err(|_| UciError::ParseError)?);
+            match keyword {
+                "default" => default = value,
+                "min" => min = value,
+                _ => max = value,
+            }
+        }
+        Ok(Spin {
+            default: default.ok_or(UciError::MissingParameter)?,
+            min: min.ok_or(UciError::MissingParameter)?,
+            max: max.ok_or(UciError::MissingParameter)?,
+        })
+    }
+}
+
+impl Display for Spin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "default {} min {} max {}", self.default, self.min, self.max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub default: bool,
+}
+
+impl Check {
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        if parts.next().ok_or(UciError::MissingParameter)? != "default" {
+            return Err(UciError::InvalidParameter);
+        }
+        Ok(Check { default: parts.next().ok_or(UciError::MissingValue)?.parse::<bool>().map_err(|_| UciError::ParseError)? })
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "default {}", self.default)
+    }
+}
+
+/// One of a list of values, which may have spaces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combo {
+    pub default: String,
+    pub vars: Vec<String>,
+}
+
+impl Combo {
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        let mut default = None;
+        let mut vars = vec![];
+        for (keyword, value) in keyword_values(parts, &["default", "var"])? {
+            if value.is_empty() {
+                return Err(UciError::MissingValue);
+            }
+            match keyword {
+                "default" => default = Some(value),
+                _ => vars.push(value),
+            }
+        }
+        Ok(Combo { default: default.ok_or(UciError::MissingParameter)?, vars })
+    }
+}
+
+impl Display for Combo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "default {}", self.default)?;
+        for var in self.vars.iter() {
+            write!(f, " var {var}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Free text, ie. a file path. The default runs to the end of the line and `<empty>` stands for
+/// an empty string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+    pub default: String,
+}
+
+impl Text {
+    const EMPTY: &str = "<empty>";
+
+    fn parse(parts: &mut SplitAsciiWhitespace) -> Result<Self, UciError> {
+        if parts.next().ok_or(UciError::MissingParameter)? != "default" {
+            return Err(UciError::InvalidParameter);
+        }
+        let default = parts.collect::<Vec<_>>().join(" ");
+        Ok(Text { default: if default == Self::EMPTY { String::new() } else { default } })
+    }
+}
+
+impl Display for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.default.as_str() {
+            "" => write!(f, "default {}", Self::EMPTY),
+            default => write!(f, "default {default}"),
+        }
+    }
+}
+
+#[test]
+fn info_events_round_trip() {
+    let round_trip = |info: InfoEvent| {
+        let line = UciEvent::Info(Box::new(info)).to_string();
+        assert_eq!(line.trim_end(), line, "no trailing space");
+        match UciEvent::parse(line.clone()) {
+            Ok(UciEvent::Info(parsed)) => (line, *parsed),
+            other => panic!("expected an info event from {line:?}, got {other:?}"),
+        }
+    };
+
+    let info = InfoEvent::default()
+        .depth(12)
+        .sel_depth(20)
+        .time(1500)
+        .nodes(2_000_000)
+        .multi_pv(2)
+        .score(ScoreEvent::cp(-35).bound(ScoreEventBound::Upper))
+        .wdl(WdlEvent { win: 20, draw: 700, loss: 280 })
+        .hash_full(420)
+        .nps(1_333_333)
+        .tb_hits(3)
+        .cpu_load(950)
+        .pv(["e2e4", "e7e5", "g1f3"]);
+    let (line, parsed) = round_trip(info);
+    assert_eq!(line, "info depth 12 seldepth 20 time 1500 nodes 2000000 multipv 2 score cp -35 upperbound wdl 20 700 280 hashfull 420 nps 1333333 tbhits 3 cpuload 950 pv e2e4 e7e5 g1f3");
+    assert_eq!(parsed.score, Some(ScoreEvent::cp(-35).bound(ScoreEventBound::Upper)));
+    assert_eq!(parsed.to_string(), line["info ".len()..]);
+
+    let info = InfoEvent::default()
+        .score(ScoreEvent::mate(-3))
+        .curr_move("e2e4")
+        .curr_move_number(1)
+        .sb_hits(7)
+        .refutation(["d1h5", "g6h5"])
+        .curr_line(Some(1), ["d1h5", "h7h6"])
+        .string("searching with 4 threads");
+    let expected = InfoEvent::default()
+        .score(ScoreEvent::mate(-3))
+        .curr_move("e2e4")
+        .curr_move_number(1)
+        .sb_hits(7)
+        .refutation(["d1h5", "g6h5"])
+        .curr_line(Some(1), ["d1h5", "h7h6"])
+        .string("searching with 4 threads");
+    assert_eq!(round_trip(info).1, expected);
+    assert!(UciEvent::parse("info depth".to_string()).is_err());
+    assert!(UciEvent::parse("info score lowerbound".to_string()).is_err());
+    match UciEvent::parse("info depth 9 ebf 1.85 nodes 100 pv e2e4".to_string()) {
+        Ok(UciEvent::Info(info)) => assert_eq!(*info, InfoEvent::default().depth(9).nodes(100).pv(["e2e4"])),
+        other => panic!("unknown fields should be skipped, got {other:?}"),
+    }
+}
+
+#[test]
+fn events_parse_with_full_fidelity() {
+    let parse = |line: &str| {
+        let event = UciEvent::parse(line.to_string()).unwrap_or_else(|e| panic!("failed to parse {line:?}: {e}"));
+        assert_eq!(event.to_string(), line, "events are written back unchanged");
+        event
+    };
+
+    assert_eq!(parse("id name Stockfish 17"), UciEvent::Id(IdEvent::Name("Stockfish 17".to_string())));
+    assert_eq!(parse("id author the Stockfish developers"), UciEvent::Id(IdEvent::Author("the Stockfish developers".to_string())));
+    assert_eq!(parse("bestmove e2e4"), UciEvent::BestMove { best: "e2e4".to_string(), ponder: None });
+    assert_eq!(parse("bestmove e7e8q ponder a2a1n"), UciEvent::BestMove { best: "e7e8q".to_string(), ponder: Some("a2a1n".to_string()) });
+    assert_eq!(parse("copyprotection checking"), UciEvent::CopyProtection(CheckStatus::Checking));
+    assert_eq!(parse("registration error"), UciEvent::Registration(CheckStatus::Error));
+    assert_eq!(parse("option name Skill Level type spin default 20 min -20 max 20"), UciEvent::Option(OptionEvent::new("Skill Level", OptionKind::Spin(Spin { default: 20, min: -20, max: 20 }))));
+    assert_eq!(parse("option name Style type combo default Very Solid var Very Solid var Risky"), UciEvent::Option(OptionEvent::new("Style", OptionKind::Combo(Combo {
+        default: "Very Solid".to_string(),
+        vars: vec!["Very Solid".to_string(), "Risky".to_string()],
+    }))));
+    assert_eq!(parse("option name SyzygyPath type string default <empty>"), UciEvent::Option(OptionEvent::new("SyzygyPath", OptionKind::String(Text { default: String::new() }))));
+    assert_eq!(parse("option name Clear Hash type button"), UciEvent::Option(OptionEvent::new("Clear Hash", OptionKind::Button)));
+
+    let spin = UciEvent::parse("option name Hash type spin min 1 max 1024 default 16".to_string());
+    assert!(matches!(spin, Ok(UciEvent::Option(OptionEvent { kind: OptionKind::Spin(Spin { default: 16, min: 1, max: 1024 }), .. }))), "keywords come in any order");
+    assert!(UciEvent::parse("bestmove e2e4 e7e5".to_string()).is_err());
+    assert!(UciEvent::parse("id name".to_string()).is_err());
+    assert!(UciEvent::parse("copyprotection maybe".to_string()).is_err());
+    assert!(UciEvent::parse("option name Hash type spin default 16".to_string()).is_err());
+    assert!(matches!(UciEvent::parse("Stockfish 17 by the Stockfish developers".to_string()), Err(UciError::UnknownCommand(_))));
+}
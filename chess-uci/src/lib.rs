@@ -0,0 +1,54 @@
+//! The Universal Chess Interface, parsing and writing the [UciCommand]s a GUI sends and the
+//! [UciEvent]s an engine answers with. Engines read commands from stdin, tools driving another
+//! engine use [client::EngineProcess].
+//!
+//! UCI Protocol Reference: https://backscattering.de/chess/uci/
+
+use std::fmt::Display;
+
+pub mod client;
+mod command;
+mod event;
+
+pub use command::*;
+pub use event::*;
+
+#[derive(Debug)]
+pub enum UciError {
+    /// A line without a known command.
+    UnknownCommand(String),
+    ParseError,
+    EmptyEvent,
+    EmptyCommand,
+    MissingValue,
+    InvalidParameter,
+    MissingParameter,
+    UnknownCommandArg,
+    MissingCommandArgs,
+    ExcessiveCommandArgs,
+    /// The FEN of a `position` command could not be loaded.
+    InvalidFen(String),
+    /// A move of a `position` command is not legal in its position.
+    IllegalMove(String),
+}
+
+impl Display for UciError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciError::UnknownCommand(line) => write!(f, "unknown command: {line}"),
+            UciError::ParseError => write!(f, "malformed value"),
+            UciError::EmptyEvent => write!(f, "empty event"),
+            UciError::EmptyCommand => write!(f, "empty command"),
+            UciError::MissingValue => write!(f, "missing value"),
+            UciError::InvalidParameter => write!(f, "invalid parameter"),
+            UciError::MissingParameter => write!(f, "missing parameter"),
+            UciError::UnknownCommandArg => write!(f, "unknown argument"),
+            UciError::MissingCommandArgs => write!(f, "missing arguments"),
+            UciError::ExcessiveCommandArgs => write!(f, "too many arguments"),
+            UciError::InvalidFen(fen) => write!(f, "invalid FEN: {fen}"),
+            UciError::IllegalMove(m) => write!(f, "illegal move: {m}"),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
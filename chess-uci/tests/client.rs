@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use chess_uci::{client::{BestMove, EngineProcess}, Combo, GoCommand, GoKind, OptionKind, ScoreEvent, ScoreEventBound, Spin, Text, UciCommand, UciEvent};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn client_drives_an_engine() {
+    let mut engine = EngineProcess::launch(env!("CARGO_BIN_EXE_fake-engine"), [] as [&str; 0], TIMEOUT).expect("handshake should succeed");
+    assert_eq!(engine.name(), Some("Fake Engine 1.0"));
+    assert_eq!(engine.author(), Some("The chess-uci Authors"));
+    let kinds: Vec<_> = engine.options().iter().map(|option| &option.kind).collect();
+    assert_eq!(kinds, [
+        &OptionKind::Spin(Spin { default: 20, min: -20, max: 20 }),
+        &OptionKind::Combo(Combo { default: "Very Solid".to_string(), vars: vec!["Very Solid".to_string(), "Risky".to_string()] }),
+        &OptionKind::String(Text { default: String::new() }),
+        &OptionKind::Button,
+        &OptionKind::Check(chess_uci::Check { default: false }),
+    ]);
+    assert!(engine.option("skill level").is_some(), "option names are not case sensitive");
+
+    engine.set_option("Book File", Some("my book.bin")).expect("engine should be running");
+    match engine.recv(TIMEOUT) {
+        Ok(UciEvent::Info(info)) => assert_eq!(&*info.string, "setoption name Book File value my book.bin"),
+        other => panic!("expected the option echoed back, got {other:?}"),
+    }
+    engine.is_ready(TIMEOUT).expect("engine should answer isready");
+
+    engine.send(&UciCommand::UciNewGame).expect("engine should be running");
+    let error = engine.recv(TIMEOUT).expect_err("malformed events should be reported");
+    assert!(error.to_string().contains("info depth many"), "{error}");
+
+    let mut infos = vec![];
+    let best = engine.go(GoCommand { kind: Some(GoKind::Depth(2)), params: vec![] }, TIMEOUT, |info| infos.push(info.score.as_ref().map(ScoreEvent::to_string)));
+    assert_eq!(best.expect("engine should answer go"), BestMove { best: "e2e4".to_string(), ponder: Some("e7e5".to_string()) });
+    assert_eq!(infos, [Some(ScoreEvent::cp(20).to_string()), None, Some(ScoreEvent::mate(2).bound(ScoreEventBound::Lower).to_string())]);
+
+    assert!(engine.quit(TIMEOUT).expect("engine should quit").success());
+}